@@ -254,13 +254,18 @@ fn test_initial_board_to_float_array() {
 fn test_multi_queen() {
     let board = BoardState::parse_fen("8/8/8/8/2QQQ3/8/8/8").unwrap();
     let representation: BoardRepresentation = board.into();
+    // the third queen doesn't fit in the queen slots and takes the first pawn slot
     expect!(
         representation,
         r#"
         BoardRepresentation {
             white: BoardRepresentationSide {
                 pawns: [
-                    Missing,
+                    Stationary {
+                        x: 4.0,
+                        y: 4.0,
+                        cooldown: 0.0,
+                    },
                     Missing,
                     Missing,
                     Missing,
@@ -331,3 +336,42 @@ fn test_multi_queen() {
         }"#
     )
 }
+
+#[test]
+fn test_three_queens_and_three_rooks() {
+    // two pawns of each side promoted to a queen and a rook
+    let board = BoardState::parse_fen("rrrqqqk1/pppppp2/8/8/8/8/PPPPPP2/RRRQQQK1").unwrap();
+    let representation: BoardRepresentation = board.into();
+    let floats = representation.to_float_array();
+    // every piece is in a slot, with the extra queen and rook in the last free pawn slots
+    expect!(floats.iter().sum::<f32>(), "26.0");
+    expect!(
+        floats
+            .chunks(BoardRepresentationSide::num_pieces())
+            .map(|side| side[..8].to_vec())
+            .collect_vec(),
+        r#"
+        [
+            [
+                1.0,
+                1.0,
+                1.0,
+                1.0,
+                1.0,
+                1.0,
+                1.0,
+                1.0,
+            ],
+            [
+                1.0,
+                1.0,
+                1.0,
+                1.0,
+                1.0,
+                1.0,
+                1.0,
+                1.0,
+            ],
+        ]"#
+    );
+}
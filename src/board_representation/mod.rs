@@ -1,7 +1,5 @@
 core!();
 
-use enum_map::Enum;
-
 use crate::*;
 
 #[cfg(test)]
@@ -66,43 +64,15 @@ impl From<BoardState> for BoardRepresentation {
 impl From<&BoardState> for BoardRepresentation {
     fn from(state: &BoardState) -> Self {
         let mut board = BoardRepresentation::new();
-        let mut counter = PieceCounter::new();
         for piece in state.pieces() {
-            let Piece { side, kind, .. } = piece;
-            let count = counter.get_mut(*side, *kind);
             board
                 .get_side_mut(piece.side)
-                .insert_piece(piece.kind, *count, piece.state.into());
-            *count += 1;
+                .insert_piece(piece.kind, piece.state.into());
         }
         board
     }
 }
 
-const NUM_SIDES: usize = std::mem::variant_count::<Side>();
-const NUM_KINDS: usize = std::mem::variant_count::<PieceKind>();
-struct PieceCounter {
-    counts: [usize; NUM_SIDES * NUM_KINDS],
-}
-
-impl PieceCounter {
-    pub fn new() -> Self {
-        Self {
-            counts: [0; NUM_SIDES * NUM_KINDS],
-        }
-    }
-    pub fn get_mut(&mut self, side: Side, kind: PieceKind) -> &mut usize {
-        let i = Side::into_usize(side) * NUM_KINDS + PieceKind::into_usize(kind);
-        &mut self.counts[i]
-    }
-}
-
-impl Default for PieceCounter {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 #[derive(Debug)]
 struct BoardRepresentationSide {
     // pawn -> 8 slots
@@ -120,10 +90,23 @@ struct BoardRepresentationSide {
 }
 
 impl BoardRepresentationSide {
-    fn insert_piece(&mut self, kind: PieceKind, position: usize, piece: BoardRepresentationPiece) {
-        let array = self.get_array_mut(kind);
-        if position < array.len() {
-            array[position] = piece;
+    // a promoted piece that doesn't fit in the slots of its kind takes a pawn slot: every
+    // promotion removes a pawn, so a legal position always has one free.
+    // pieces beyond that (only possible in a hand-written position) are left out.
+    // the model can't tell such a piece from a pawn, so both cases are reported
+    fn insert_piece(&mut self, kind: PieceKind, piece: BoardRepresentationPiece) {
+        let free_slot = |array: &mut [BoardRepresentationPiece]| {
+            array
+                .iter()
+                .position(|slot| matches!(slot, BoardRepresentationPiece::Missing))
+        };
+        if let Some(i) = free_slot(self.get_array_mut(kind)) {
+            self.get_array_mut(kind)[i] = piece;
+        } else if let Some(i) = free_slot(&mut self.pawns) {
+            println!("No {kind:?} slot left in the board representation, using a pawn slot");
+            self.pawns[i] = piece;
+        } else {
+            println!("No slot left in the board representation for a {kind:?}, leaving it out");
         }
     }
     fn get_array_mut(&mut self, kind: PieceKind) -> &mut [BoardRepresentationPiece] {
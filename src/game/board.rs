@@ -1,6 +1,8 @@
 core!();
 
 use core::slice;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
 
 use super::*;
 use enum_map::EnumMap;
@@ -12,6 +14,11 @@ pub struct BoardState {
     pieces: Vec<Piece>,
     can_long_castle: EnumMap<Side, bool>,
     can_short_castle: EnumMap<Side, bool>,
+    castling_files: CastlingFiles,
+    occupancy: Occupancy, // kept in sync with pieces
+    hash: ZobristHash,    // kept in sync with pieces and castling rights
+    config: GameConfig,
+    journal: Option<Vec<PieceChange>>, // only recording while a reversible step is running
 }
 
 // has to be less than sqrt(2)/2 to ensure bishops do not capture squares
@@ -24,22 +31,80 @@ impl BoardState {
     }
 
     // TODO-someday: For_test module?
-    pub fn pieces_mut(&mut self) -> &mut Vec<Piece> {
-        &mut self.pieces
+    pub fn pieces_mut(&mut self) -> PiecesMut<'_> {
+        debug_assert!(self.journal.is_none());
+        PiecesMut { board: self }
     }
 
-    fn new_with_castling(pieces: Vec<Piece>, enable_castling: bool) -> Self {
-        Self {
+    pub(super) fn new(
+        pieces: Vec<Piece>,
+        can_long_castle: EnumMap<Side, bool>,
+        can_short_castle: EnumMap<Side, bool>,
+        castling_files: CastlingFiles,
+    ) -> Self {
+        let occupancy = Occupancy::new(&pieces);
+        let mut board = Self {
+            pieces,
+            can_long_castle,
+            can_short_castle,
+            castling_files,
+            occupancy,
+            hash: 0,
+            config: GameConfig::default(),
+            journal: None,
+        };
+        board.hash = board.compute_hash();
+        board
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn with_config(self, config: GameConfig) -> Self {
+        Self { config, ..self }
+    }
+
+    pub(super) fn new_with_castling(pieces: Vec<Piece>, enable_castling: bool) -> Self {
+        Self::new(
             pieces,
-            can_long_castle: enum_map! {
+            enum_map! {
                 Side::White => enable_castling,
                 Side::Black => enable_castling,
             },
-            can_short_castle: enum_map! {
+            enum_map! {
                 Side::White => enable_castling,
                 Side::Black => enable_castling,
             },
+            CastlingFiles::STANDARD,
+        )
+    }
+
+    // recomputes everything that is derived from pieces
+    fn reindex(&mut self) {
+        self.occupancy = Occupancy::new(&self.pieces);
+        self.hash = self.compute_hash();
+    }
+
+    fn compute_hash(&self) -> ZobristHash {
+        let mut hash = self
+            .pieces
+            .iter()
+            .fold(0, |hash, piece| hash ^ piece_key(piece));
+        for side in [Side::White, Side::Black] {
+            if self.can_long_castle[side] {
+                hash ^= castling_key(side, true, &self.castling_files);
+            }
+            if self.can_short_castle[side] {
+                hash ^= castling_key(side, false, &self.castling_files);
+            }
         }
+        hash
+    }
+
+    // identifies the pieces, castling rights, cooldowns and motion, but not the config
+    pub fn zobrist_hash(&self) -> ZobristHash {
+        self.hash
     }
 
     pub fn new_initial_state() -> Self {
@@ -62,10 +127,12 @@ impl BoardState {
         }
         append_pieces(&mut pieces, Side::White, &INITIAL_WHITE_PIECES);
         append_pieces(&mut pieces, Side::Black, &INITIAL_BLACK_PIECES);
-        // TODO: Support Castling
-        Self::new_with_castling(pieces, false)
+        Self::new_with_castling(pieces, true)
     }
     pub fn can_move(&self, board_move: &BoardMove) -> bool {
+        self.validate_move(board_move).is_ok()
+    }
+    pub fn validate_move(&self, board_move: &BoardMove) -> Result<(), MoveError> {
         fn get_positions_between(start: Position, end: Position) -> Vec<Position> {
             let mut vec = Vec::new();
             let mut current = start;
@@ -81,127 +148,345 @@ impl BoardState {
             vec
         }
         match board_move {
-            BoardMove::None(_side) => true,
-            BoardMove::Normal { piece, target } => {
+            BoardMove::None(_side) => Ok(()),
+            BoardMove::Normal {
+                piece,
+                target,
+                promotion,
+            } => {
                 let target = *target;
-                match piece.state {
-                    PieceState::Moving { .. } => false,
-                    PieceState::Stationary { cooldown, .. } if cooldown > 0 => false,
-                    PieceState::Stationary { position, .. } => {
-                        if position == target {
-                            return false;
+                let Piece { side, kind, .. } = *piece;
+                let position = match piece.state {
+                    PieceState::Moving { .. } => return Err(MoveError::PieceMoving { kind }),
+                    PieceState::Stationary { position, .. } => position,
+                };
+                // the move may have been made from an outdated copy of the piece
+                let cooldown = match self.get_stationary_piece(position) {
+                    Some(Piece {
+                        side: board_side,
+                        kind: board_kind,
+                        state: PieceState::Stationary { cooldown, .. },
+                    }) if *board_side == side && *board_kind == kind => *cooldown,
+                    _ => {
+                        return Err(MoveError::NoSuchPiece {
+                            side,
+                            kind,
+                            square: position,
+                        })
+                    }
+                };
+                if cooldown > 0 {
+                    return Err(MoveError::OnCooldown {
+                        kind,
+                        square: position,
+                        turns_left: cooldown,
+                    });
+                }
+                if target.x >= BOARD_SIZE as u32 || target.y >= BOARD_SIZE as u32 {
+                    return Err(MoveError::OffBoard { square: target });
+                }
+                if position == target {
+                    return Err(MoveError::SameSquare {
+                        kind,
+                        square: target,
+                    });
+                }
+                // check that target is not occupied by friendly unit
+                if self.has_stationary_piece_on_side(target, side) {
+                    return Err(MoveError::OccupiedByFriendly { square: target });
+                }
+                // Ensure that no moving pieces has this target as the target
+                if !self.is_valid_destination(side, target) {
+                    return Err(MoveError::ReservedByFriendly { square: target });
+                }
+                if !self.is_valid_promotion(piece, target, *promotion) {
+                    return Err(MoveError::InvalidPromotion {
+                        promotion: *promotion,
+                    });
+                }
+                let illegal_geometry = MoveError::IllegalGeometry {
+                    kind,
+                    from: position,
+                    to: target,
+                };
+                let delta = target - position;
+                match kind {
+                    PieceKind::Pawn => {
+                        let is_capturing_enemy =
+                            self.has_stationary_piece_on_side(target, side.opposite());
+                        let is_in_starting_rank = position.y
+                            == match side {
+                                Side::White => BOARD_SIZE as u32 - 2,
+                                Side::Black => 1,
+                            };
+                        let is_forward_move = delta.x == 0
+                            && (delta.y == forward_y(side)
+                                || (delta.y == forward_y(side) * 2 && is_in_starting_rank));
+                        let is_capture_move =
+                            delta.x.abs() == 1 && delta.y == forward_y(side) && is_capturing_enemy;
+                        if is_forward_move && is_capturing_enemy {
+                            Err(MoveError::PathBlocked {
+                                from: position,
+                                to: target,
+                                square: target,
+                            })
+                        } else if is_forward_move || is_capture_move {
+                            Ok(())
+                        } else {
+                            Err(illegal_geometry)
+                        }
+                    }
+                    PieceKind::Knight => {
+                        let abs_x = delta.x.abs();
+                        let abs_y = delta.y.abs();
+                        if (abs_x == 1 && abs_y == 2) || (abs_x == 2 && abs_y == 1) {
+                            Ok(())
+                        } else {
+                            Err(illegal_geometry)
                         }
-                        // check that target is not occupied by friendly unit
-                        // Ensure that no moving pieces has this target as the target
-                        if !self.is_valid_destination(piece.side, target) {
-                            return false;
+                    }
+                    PieceKind::Bishop | PieceKind::Rook | PieceKind::Queen => {
+                        // check if the piece can go on this delta
+                        let is_straight = delta.x == 0 || delta.y == 0;
+                        let is_diagonal = delta.x.abs() == delta.y.abs();
+                        let allowed_delta = match kind {
+                            PieceKind::Bishop => is_diagonal,
+                            PieceKind::Rook => is_straight,
+                            PieceKind::Queen => is_straight || is_diagonal,
+                            _ => false,
+                        };
+                        if !allowed_delta {
+                            return Err(illegal_geometry);
                         }
-                        let target_piece = self.get_stationary_piece(target); // enemy piece
-                        debug_assert!(
-                            target_piece.map_or(true, |enemy_piece| enemy_piece.side != piece.side)
-                        );
-                        match piece.kind {
-                            PieceKind::Pawn => {
-                                let delta = target - position;
-                                let is_capturing_enemy = target_piece
-                                    .map_or(false, |target_piece| piece.side != target_piece.side);
-                                let can_normal_move = delta.y == forward_y(piece.side)
-                                    && ((delta.x == 0 && !is_capturing_enemy)
-                                        || (delta.x.abs() == 1 && is_capturing_enemy));
-                                let is_in_starting_rank = position.y
-                                    == match piece.side {
-                                        Side::White => BOARD_SIZE as u32 - 2,
-                                        Side::Black => 1,
-                                    };
-                                let can_double_move = delta.x == 0
-                                    && delta.y == forward_y(piece.side) * 2
-                                    && !is_capturing_enemy
-                                    && is_in_starting_rank;
-                                can_normal_move || can_double_move
-                            }
-                            PieceKind::Knight => {
-                                let delta = target - position;
-                                let abs_x = delta.x.abs();
-                                let abs_y = delta.y.abs();
-                                (abs_x == 1 && abs_y == 2) || (abs_x == 2 && abs_y == 1)
-                            }
-                            PieceKind::Bishop | PieceKind::Rook | PieceKind::Queen => {
-                                // check if the piece can go on this delta
-                                let delta = target - position;
-                                let is_straight = delta.x == 0 || delta.y == 0;
-                                let is_diagonal = delta.x.abs() == delta.y.abs();
-                                let allowed_delta = match piece.kind {
-                                    PieceKind::Bishop => is_diagonal,
-                                    PieceKind::Rook => is_straight,
-                                    PieceKind::Queen => is_straight || is_diagonal,
-                                    _ => false,
-                                };
-                                if allowed_delta {
-                                    // check if every square in its path is not occupied by unit
-                                    let path_is_occupied = get_positions_between(position, target)
-                                        .iter()
-                                        .any(|&pos| {
-                                            self.get_piece_including_moving_on_side(pos, piece.side)
-                                                .is_some()
-                                        });
-                                    !path_is_occupied
-                                } else {
-                                    false
-                                }
-                            }
-                            PieceKind::King => {
-                                let delta = target - position;
-                                delta.x * delta.x + delta.y * delta.y <= 2
-                            }
+                        // check if every square in its path is not occupied by unit
+                        let blocked_square = get_positions_between(position, target)
+                            .into_iter()
+                            .find(|&pos| self.is_occupied_including_moving_on_side(pos, side));
+                        match blocked_square {
+                            Some(square) => Err(MoveError::PathBlocked {
+                                from: position,
+                                to: target,
+                                square,
+                            }),
+                            None => Ok(()),
+                        }
+                    }
+                    PieceKind::King => {
+                        if delta.x * delta.x + delta.y * delta.y <= 2 {
+                            Ok(())
+                        } else {
+                            Err(illegal_geometry)
                         }
                     }
                 }
             }
-            BoardMove::LongCastle(_side) => false, // TODO: do castling
-            BoardMove::ShortCastle(_side) => false, // TODO: do castling
+            BoardMove::LongCastle(side) => self.validate_castle(*side, true),
+            BoardMove::ShortCastle(side) => self.validate_castle(*side, false),
+        }
+    }
+    fn can_castle(&self, side: Side, is_long_castle: bool) -> bool {
+        self.validate_castle(side, is_long_castle).is_ok()
+    }
+    fn validate_castle(&self, side: Side, is_long_castle: bool) -> Result<(), MoveError> {
+        let has_castling_right = if is_long_castle {
+            self.can_long_castle[side]
+        } else {
+            self.can_short_castle[side]
+        };
+        if !has_castling_right {
+            return Err(MoveError::NoCastlingRight {
+                side,
+                is_long_castle,
+            });
+        }
+        let CastlingPositions {
+            king_start,
+            king_end,
+            rook_start,
+            rook_end,
+        } = CastlingPositions::new(side, is_long_castle, &self.castling_files);
+        let is_ready = |position: Position, kind: PieceKind| {
+            self.get_stationary_piece(position).map_or(false, |piece| {
+                piece.side == side
+                    && piece.kind == kind
+                    && matches!(piece.state, PieceState::Stationary { cooldown, .. } if cooldown == 0)
+            })
+        };
+        if !is_ready(king_start, PieceKind::King) || !is_ready(rook_start, PieceKind::Rook) {
+            return Err(MoveError::CastlingPiecesNotReady {
+                side,
+                is_long_castle,
+            });
+        }
+        // every square the king or the rook starts on, ends on or passes over (other than where
+        // the two of them start) must be empty and not reserved by one of our moving pieces
+        let files = [king_start.x, king_end.x, rook_start.x, rook_end.x];
+        let min_x = files.into_iter().min().unwrap();
+        let max_x = files.into_iter().max().unwrap();
+        let blocked_square = (min_x..=max_x)
+            .filter(|&x| x != king_start.x && x != rook_start.x)
+            .map(|x| Position::from((x, king_start.y)))
+            .find(|&square| self.is_occupied_including_moving_on_side(square, side));
+        match blocked_square {
+            Some(square) => Err(MoveError::CastlingPathBlocked {
+                side,
+                is_long_castle,
+                square,
+            }),
+            None => Ok(()),
         }
     }
     pub fn apply_move(&mut self, board_move: &BoardMove) {
         match board_move {
             BoardMove::None(_side) => {}
-            BoardMove::LongCastle(_side) => {
-                // TODO
-                // let rook_position = match side {
-                //     Side::White => (0f32, 0f32),
-                //     Side::Black => (0f32, 0f32),
-                // };
-                // get starting positions of rook and king
-            }
-            BoardMove::ShortCastle(_side) => {}
-            BoardMove::Normal { piece, target } => {
+            BoardMove::LongCastle(side) => self.apply_castle(*side, true),
+            BoardMove::ShortCastle(side) => self.apply_castle(*side, false),
+            BoardMove::Normal {
+                piece,
+                target,
+                promotion,
+            } => {
                 let target = *target;
                 // ensure that the piece is not already moving
                 debug_assert!(piece.state.is_stationary());
                 // ensure that the target is not of a piece on our own side
                 debug_assert!(self.is_valid_destination(piece.side, target));
+                debug_assert!(self.is_valid_promotion(piece, target, *promotion));
                 if let PieceState::Stationary { position, .. } = piece.state {
-                    let Position { x, y } = position;
-                    let delta = target - position;
-                    let target = MoveTarget::new(
-                        position,
-                        target,
-                        delta.dist_linf(),
-                        MoveTarget::MIN_PRIORITY,
-                    );
-                    // TODO: somehow optimize so we don't have to loop through?
-                    if let Some(piece) = self.get_stationary_piece_mut(position) {
-                        piece.state = PieceState::Moving {
-                            x: x as f32,
-                            y: y as f32,
-                            target,
-                        }
-                    }
-                    // TODO: invalidate any castling if needed
+                    self.start_moving(position, target, *promotion);
+                    self.update_castling_rights();
                 };
             }
         }
     }
+    fn apply_castle(&mut self, side: Side, is_long_castle: bool) {
+        debug_assert!(self.can_castle(side, is_long_castle));
+        let CastlingPositions {
+            king_start,
+            king_end,
+            rook_start,
+            rook_end,
+        } = CastlingPositions::new(side, is_long_castle, &self.castling_files);
+        // both pieces leave at the same time, each travelling at its usual speed
+        // (in Chess960 one of them can already be on its destination, and then it stays)
+        for (start, end) in [(king_start, king_end), (rook_start, rook_end)] {
+            if start != end {
+                self.start_moving(start, end, None);
+            }
+        }
+        self.revoke_castling_right(side, true);
+        self.revoke_castling_right(side, false);
+    }
+    fn start_moving(&mut self, position: Position, target: Position, promotion: Option<PieceKind>) {
+        let Position { x, y } = position;
+        let config = self.config;
+        // TODO: somehow optimize so we don't have to loop through?
+        if let Some(index) = self.get_stationary_piece_index(position) {
+            let piece = &mut self.pieces[index];
+            if let Some(journal) = &mut self.journal {
+                journal.push(PieceChange::Changed {
+                    index,
+                    piece: *piece,
+                });
+            }
+            let target = MoveTarget {
+                promotion,
+                ..MoveTarget::new(
+                    &config,
+                    piece.kind,
+                    position,
+                    target,
+                    MoveTarget::MIN_PRIORITY,
+                )
+            };
+            let before = *piece;
+            piece.state = PieceState::Moving {
+                x: x as f32,
+                y: y as f32,
+                target,
+            };
+            let after = *piece;
+            self.occupancy.remove(&before);
+            self.occupancy.add(&after);
+            self.hash ^= piece_key(&before) ^ piece_key(&after);
+        }
+    }
+    fn is_promotion_move(piece: &Piece, target: Position) -> bool {
+        piece.kind == PieceKind::Pawn && target.y == promotion_y(piece.side)
+    }
+    // a promotion kind must be given exactly when the rules let the player choose one
+    fn is_valid_promotion(
+        &self,
+        piece: &Piece,
+        target: Position,
+        promotion: Option<PieceKind>,
+    ) -> bool {
+        if Self::is_promotion_move(piece, target) {
+            self.config.promotion_choices().contains(&promotion)
+        } else {
+            promotion.is_none()
+        }
+    }
+    fn get_promotion_choices(&self, piece: &Piece, target: Position) -> Vec<Option<PieceKind>> {
+        if Self::is_promotion_move(piece, target) {
+            self.config.promotion_choices()
+        } else {
+            vec![None]
+        }
+    }
+    // revokes castling rights once the king or the rook has left (or been captured on) its starting square
+    fn update_castling_rights(&mut self) {
+        for side in [Side::White, Side::Black] {
+            if self.can_long_castle[side] && !self.has_castling_pieces(side, true) {
+                self.revoke_castling_right(side, true);
+            }
+            if self.can_short_castle[side] && !self.has_castling_pieces(side, false) {
+                self.revoke_castling_right(side, false);
+            }
+        }
+    }
+    fn revoke_castling_right(&mut self, side: Side, is_long_castle: bool) {
+        let can_castle = if is_long_castle {
+            &mut self.can_long_castle[side]
+        } else {
+            &mut self.can_short_castle[side]
+        };
+        if *can_castle {
+            *can_castle = false;
+            self.hash ^= castling_key(side, is_long_castle, &self.castling_files);
+        }
+    }
+    fn has_castling_pieces(&self, side: Side, is_long_castle: bool) -> bool {
+        let CastlingPositions {
+            king_start,
+            rook_start,
+            ..
+        } = CastlingPositions::new(side, is_long_castle, &self.castling_files);
+        let is_present = |position: Position, kind: PieceKind| {
+            self.get_stationary_piece(position)
+                .map_or(false, |piece| piece.side == side && piece.kind == kind)
+        };
+        is_present(king_start, PieceKind::King) && is_present(rook_start, PieceKind::Rook)
+    }
+    pub fn can_long_castle(&self, side: Side) -> bool {
+        self.can_long_castle[side]
+    }
+    pub fn can_short_castle(&self, side: Side) -> bool {
+        self.can_short_castle[side]
+    }
+    pub fn castling_files(&self) -> &CastlingFiles {
+        &self.castling_files
+    }
+    fn has_any_castling_right(&self) -> bool {
+        self.can_long_castle
+            .values()
+            .chain(self.can_short_castle.values())
+            .any(|&right| right)
+    }
     fn get_stationary_piece(&self, position: Position) -> Option<&Piece> {
+        if !self.has_stationary_piece(position) {
+            return None;
+        }
         // TODO-someday: Return some stationary piece type instead of just piece?
         self.pieces.iter().find(|piece| match piece.state {
             PieceState::Stationary {
@@ -211,8 +496,11 @@ impl BoardState {
             PieceState::Moving { .. } => false,
         })
     }
-    fn get_stationary_piece_mut(&mut self, position: Position) -> Option<&mut Piece> {
-        self.pieces.iter_mut().find(|piece| match piece.state {
+    fn get_stationary_piece_index(&self, position: Position) -> Option<usize> {
+        if !self.has_stationary_piece(position) {
+            return None;
+        }
+        self.pieces.iter().position(|piece| match piece.state {
             PieceState::Stationary {
                 position: piece_position,
                 ..
@@ -220,17 +508,16 @@ impl BoardState {
             PieceState::Moving { .. } => false,
         })
     }
-    fn get_piece_including_moving_on_side(&self, position: Position, side: Side) -> Option<&Piece> {
-        self.pieces.iter().find(|piece| match piece.state {
-            PieceState::Stationary {
-                position: piece_position,
-                ..
-            } => position == piece_position,
-            PieceState::Moving {
-                target: MoveTarget { target, .. },
-                ..
-            } => side == piece.side && position == target,
-        })
+    fn has_stationary_piece(&self, position: Position) -> bool {
+        self.occupancy.all_stationary() & to_bitboard(position) != 0
+    }
+    fn has_stationary_piece_on_side(&self, position: Position, side: Side) -> bool {
+        self.occupancy.stationary(side) & to_bitboard(position) != 0
+    }
+    // whether there is a stationary piece of any side, or a moving piece of the given side targeting the position
+    fn is_occupied_including_moving_on_side(&self, position: Position, side: Side) -> bool {
+        (self.occupancy.all_stationary() | self.occupancy.reserved(side)) & to_bitboard(position)
+            != 0
     }
     pub fn step_until_one_becomes_stationary(&mut self) -> bool {
         let min_turns_left = self
@@ -255,100 +542,186 @@ impl BoardState {
             false
         }
     }
-    fn handle_intersections_for_singular_moving_piece(&mut self, n: f32) {
-        fn is_within_distance_squared(
-            p @ (x, y): (f32, f32),
-            v @ (vx, vy): (f32, f32),
-            n: f32,
-        ) -> bool {
-            let t = get_t_clamped(p, v, n);
-            let intersection_x = x + vx * t;
-            let intersection_y = y + vy * t;
-            intersection_x * intersection_x + intersection_y * intersection_y
-                <= DISTANCE_THRESHOLD_SQUARED
-        }
-        fn get_t_clamped((x, y): (f32, f32), (vx, vy): (f32, f32), n: f32) -> f32 {
-            // at what t will p + v * t be closest to (0, 0)?
-            // t = (-px * vx - py * vy) / (vx^2 + vy^2)
-            ((-x * vx - y * vy) / (vx * vx + vy * vy)).clamp(0f32, n)
+    // number of ticks that can be skipped in one go: no piece can be captured during them,
+    // and they end no later than the next arrival (None if no piece is moving)
+    fn get_ticks_until_next_event(&self) -> Option<u32> {
+        fn get_ticks_before_intersection((x, y): (f32, f32), (vx, vy): (f32, f32)) -> u32 {
+            // margin so that rounding in the positions never hides a capture
+            const ROUNDING_MARGIN: f32 = 0.01;
+            // first s >= 0 at which p + v * s comes within the capture distance of (0, 0)
+            // |p + v * s|^2 = r^2  <=>  a * s^2 + b * s + c = 0
+            let radius = DISTANCE_THRESHOLD_SQUARED.sqrt() + ROUNDING_MARGIN;
+            let a = vx * vx + vy * vy;
+            let b = 2f32 * (x * vx + y * vy);
+            let c = x * x + y * y - radius * radius;
+            if c <= 0f32 {
+                return 0;
+            }
+            let discriminant = b * b - 4f32 * a * c;
+            if a == 0f32 || discriminant < 0f32 {
+                return u32::MAX;
+            }
+            let s = (-b - discriminant.sqrt()) / (2f32 * a);
+            if s < 0f32 {
+                // moving away from each other
+                return u32::MAX;
+            }
+            // the tick starting at t checks [t, t + 1], so it is safe as long as t + 1 < s
+            (s.ceil() as u32).saturating_sub(1)
         }
-        // we get the moving piece, analyze the target, and remove pieces
-        let moving_piece = self
-            .pieces
-            .iter()
-            .find(|piece| piece.state.is_moving())
-            .cloned();
-        if let Some(moving_piece) = moving_piece &&
-            let PieceState::Moving {
-            x: capturer_x,
-            y: capturer_y,
-            target:
-                MoveTarget {
-                    velocity: (capturer_vx, capturer_vy),
-                    turns_left,
-                    ..
-                },
-        } = moving_piece.state
-        {
-            self.pieces.retain(|piece| {
-                if piece.side != moving_piece.side &&
-                    let PieceState::Stationary { position, .. } = piece.state {
-                    // will this piece get retained in the next n steps?
-                    // will capturer (x, y) + t * (vx, vy) intersect with position?
-                    let delta_x = capturer_x - (position.x as f32);
-                    let delta_y = capturer_y - (position.y as f32);
-                    !is_within_distance_squared(
-                        (delta_x, delta_y),
-                        (capturer_vx, capturer_vy),
-                        n.min(turns_left as f32),
-                    )
-                } else {
-                    true
+        let mut ticks: Option<u32> = None;
+        for capturer in &self.pieces {
+            if let PieceState::Moving {
+                x,
+                y,
+                target:
+                    MoveTarget {
+                        turns_left,
+                        priority,
+                        velocity: (vx, vy),
+                        ..
+                    },
+            } = capturer.state
+            {
+                // motion stays linear until the first arrival
+                let mut capturer_ticks = turns_left;
+                for piece in &self.pieces {
+                    if piece.side == capturer.side {
+                        continue;
+                    }
+                    // same as the checks in step: stationary pieces never capture, and a moving
+                    // piece can only be captured by one that started moving no later than it
+                    let (px, py, pvx, pvy) = match piece.state {
+                        PieceState::Stationary { position, .. } => {
+                            (position.x as f32, position.y as f32, 0f32, 0f32)
+                        }
+                        PieceState::Moving {
+                            target:
+                                MoveTarget {
+                                    priority: piece_priority,
+                                    ..
+                                },
+                            ..
+                        } if piece_priority > priority => continue,
+                        PieceState::Moving {
+                            x: px,
+                            y: py,
+                            target:
+                                MoveTarget {
+                                    velocity: (pvx, pvy),
+                                    ..
+                                },
+                        } => (px, py, pvx, pvy),
+                    };
+                    let piece_ticks =
+                        get_ticks_before_intersection((x - px, y - py), (vx - pvx, vy - pvy));
+                    capturer_ticks = capturer_ticks.min(piece_ticks);
                 }
-            });
+                ticks = Some(ticks.map_or(capturer_ticks, |ticks| ticks.min(capturer_ticks)));
+            }
         }
+        ticks
     }
+    // skips ahead over the ticks in which nothing can be captured, stepping one tick at a time otherwise
+    // gives the same result as calling step_without_moves until all pieces are stationary with no cooldown
     pub fn step_until_stationary_with_no_cooldown(&mut self) {
-        // TODO: check intersections among moving pieces
-        // currently just a conservative heuristic
-        let no_intersections = self
-            .pieces
-            .iter()
-            .filter(|piece| piece.state.is_moving())
-            .count()
-            <= 1;
-        if no_intersections {
-            // teleport all pieces to the end
-            self.handle_intersections_for_singular_moving_piece(f32::MAX);
-            self.advance_pieces_by_time(u32::MAX);
-        } else {
-            self.step_without_moves();
-            self.step_until_stationary_with_no_cooldown();
+        while let Some(ticks) = self.get_ticks_until_next_event() {
+            if ticks == 0 {
+                self.step_without_moves();
+            } else {
+                self.advance_pieces_by_time(ticks, |_| {});
+                self.update_castling_rights();
+            }
         }
+        // only cooldowns are left
+        self.advance_pieces_by_time(u32::MAX, |_| {});
+        self.debug_validate();
     }
+    // gives the same result as calling step_without_moves n times
     pub fn step_n(&mut self, n: u32) {
-        let no_intersections = self
-            .pieces
-            .iter()
-            .filter(|piece| piece.state.is_moving())
-            .count()
-            <= 1;
-        if no_intersections {
-            // teleport all pieces to the end
-            self.handle_intersections_for_singular_moving_piece(n as f32);
-            self.advance_pieces_by_time(n);
-            debug_assert!(!self.has_overlapping_pieces());
-        } else {
-            self.step_without_moves();
-            if n > 1 {
-                self.step_n(n - 1);
+        let mut ticks_left = n;
+        while ticks_left > 0 {
+            match self.get_ticks_until_next_event() {
+                None => {
+                    self.advance_pieces_by_time(ticks_left, |_| {});
+                    ticks_left = 0;
+                }
+                Some(0) => {
+                    self.step_without_moves();
+                    ticks_left -= 1;
+                }
+                Some(ticks) => {
+                    let ticks = ticks.min(ticks_left);
+                    self.advance_pieces_by_time(ticks, |_| {});
+                    self.update_castling_rights();
+                    ticks_left -= ticks;
+                }
+            }
+        }
+        self.debug_validate();
+    }
+    // same as step, but returns what undo needs to revert it in place
+    pub fn step_reversibly(&mut self, white_move: &BoardMove, black_move: &BoardMove) -> StepUndo {
+        self.apply_reversibly(|board| board.step(white_move, black_move))
+    }
+    // runs any combination of moves and steps so that undo can revert them in place
+    pub fn apply_reversibly<F>(&mut self, f: F) -> StepUndo
+    where
+        F: FnOnce(&mut Self),
+    {
+        debug_assert!(self.journal.is_none());
+        let can_long_castle = self.can_long_castle;
+        let can_short_castle = self.can_short_castle;
+        let occupancy = self.occupancy;
+        let hash = self.hash;
+        self.journal = Some(Vec::with_capacity(self.pieces.len()));
+        f(self);
+        StepUndo {
+            changes: self.journal.take().unwrap(),
+            can_long_castle,
+            can_short_castle,
+            occupancy,
+            hash,
+        }
+    }
+    pub fn undo(&mut self, undo: StepUndo) {
+        for change in undo.changes.into_iter().rev() {
+            match change {
+                PieceChange::Changed { index, piece } => self.pieces[index] = piece,
+                PieceChange::Captured { index, piece } => self.pieces.insert(index, piece),
             }
         }
+        self.can_long_castle = undo.can_long_castle;
+        self.can_short_castle = undo.can_short_castle;
+        self.occupancy = undo.occupancy;
+        self.hash = undo.hash;
+        debug_assert!(self.occupancy == Occupancy::new(&self.pieces));
+        debug_assert!(self.hash == self.compute_hash());
     }
     pub fn step_without_moves(&mut self) {
         self.step(&BoardMove::None(Side::White), &BoardMove::None(Side::Black));
     }
     pub fn step(&mut self, white_move: &BoardMove, black_move: &BoardMove) {
+        self.step_with_event_handler(white_move, black_move, |_| {});
+    }
+    // same as step, but also reports what happened during the step
+    pub fn step_with_events(
+        &mut self,
+        white_move: &BoardMove,
+        black_move: &BoardMove,
+    ) -> Vec<StepEvent> {
+        let mut events = Vec::new();
+        self.step_with_event_handler(white_move, black_move, |event| events.push(event));
+        events
+    }
+    fn step_with_event_handler<F>(
+        &mut self,
+        white_move: &BoardMove,
+        black_move: &BoardMove,
+        mut on_event: F,
+    ) where
+        F: FnMut(StepEvent),
+    {
         debug_assert!(white_move.side() == Side::White);
         debug_assert!(black_move.side() == Side::Black);
         self.apply_move(white_move);
@@ -497,25 +870,72 @@ impl BoardState {
                 (left, mid, right)
             }
         }
+        let mut num_kept = 0;
         retain_mut_with_others(&mut self.pieces, |piece, mut others| {
             let priority = get_priority(piece);
             let new_position = position_after_step(&piece.state);
-            // check if any intersect
-            let intersects = others.any(|capturer| {
-                piece.side != capturer.side
+            // check if any intersect (only moving pieces capture, and most pieces are stationary)
+            let capturer = others.find(|capturer| {
+                capturer.state.is_moving()
+                    && piece.side != capturer.side
                     && can_be_captured(priority, new_position, capturer)
                     && piece_will_be_captured(piece, capturer)
             });
-            !intersects
+            if let Some(capturer) = capturer {
+                self.hash ^= piece_key(piece);
+                if let Some(journal) = &mut self.journal {
+                    journal.push(PieceChange::Captured {
+                        index: num_kept,
+                        piece: *piece,
+                    });
+                }
+                on_event(StepEvent::Captured {
+                    victim: *piece,
+                    capturer: *capturer,
+                    tick: 0,
+                });
+            } else {
+                num_kept += 1;
+            }
+            capturer.is_none()
         });
-        self.advance_pieces_by_time(1);
-        debug_assert!(!self.has_overlapping_pieces());
+        self.advance_pieces_by_time(1, &mut on_event);
+        self.update_castling_rights();
+        self.debug_validate();
+        debug_assert!(self.occupancy == Occupancy::new(&self.pieces));
+        debug_assert!(self.hash == self.compute_hash());
     }
     // Does not check intersections between pieces
-    fn advance_pieces_by_time(&mut self, time: u32) {
-        for piece in &mut self.pieces {
+    fn advance_pieces_by_time<F>(&mut self, time: u32, mut on_event: F)
+    where
+        F: FnMut(StepEvent),
+    {
+        let GameConfig {
+            piece_cooldown,
+            promotion,
+            ..
+        } = self.config;
+        for (index, piece) in self.pieces.iter_mut().enumerate() {
+            // stationary pieces without cooldown are unaffected
+            let old_key = match piece.state {
+                PieceState::Stationary { cooldown: 0, .. } => None,
+                _ => Some(piece_key(piece)),
+            };
+            if old_key.is_some() && let Some(journal) = &mut self.journal {
+                journal.push(PieceChange::Changed {
+                    index,
+                    piece: *piece,
+                });
+            }
+            // (square, tick) of things that happened to this piece, reported once it is updated
+            let mut arrival = None;
+            let mut cooldown_expiry = None;
+            let mut is_promoted = false;
             match &mut piece.state {
-                PieceState::Stationary { cooldown, .. } => {
+                PieceState::Stationary { position, cooldown } => {
+                    if *cooldown > 0 && *cooldown <= time {
+                        cooldown_expiry = Some((*position, *cooldown - 1));
+                    }
                     // decrement cooldown
                     *cooldown = cooldown.saturating_sub(time);
                 }
@@ -528,43 +948,79 @@ impl BoardState {
                             turns_left,
                             priority,
                             velocity: (vx, vy),
+                            promotion: chosen_promotion,
                         },
                 } => {
                     if *turns_left <= time {
+                        let square = *target;
+                        let arrival_tick = *turns_left - 1;
+                        let ticks_since_arrival = time - *turns_left;
                         // check if it's a pawn promotion
-                        let is_pawn_promotion = piece.kind == PieceKind::Pawn
-                            && target.y
-                                == match piece.side {
-                                    Side::White => 0u32,
-                                    Side::Black => BOARD_SIZE as u32 - 1u32,
-                                };
-                        if is_pawn_promotion {
-                            piece.kind = PieceKind::Queen;
+                        if piece.kind == PieceKind::Pawn && square.y == promotion_y(piece.side) {
+                            let kind = match (*chosen_promotion, promotion) {
+                                (Some(kind), _) => kind,
+                                (None, PromotionRule::Always(kind)) => kind,
+                                (None, PromotionRule::Never) => PieceKind::Pawn,
+                                // only reachable for hand-built states, so fall back to the usual choice
+                                (None, PromotionRule::Choice) => PieceKind::Queen,
+                            };
+                            is_promoted = kind != PieceKind::Pawn;
+                            piece.kind = kind;
                         }
                         piece.state = PieceState::Stationary {
-                            position: *target,
-                            cooldown: PIECE_COOLDOWN.saturating_sub(time - *turns_left),
+                            position: square,
+                            cooldown: piece_cooldown.saturating_sub(ticks_since_arrival),
+                        };
+                        arrival = Some((square, arrival_tick));
+                        if piece_cooldown > 0 && ticks_since_arrival >= piece_cooldown {
+                            cooldown_expiry = Some((square, arrival_tick + piece_cooldown));
                         }
                     } else {
-                        *x += (time as f32) * *vx;
-                        *y += (time as f32) * *vy;
+                        // one tick at a time so that skipping ahead rounds exactly like stepping
+                        for _ in 0..time {
+                            *x += *vx;
+                            *y += *vy;
+                        }
                         *turns_left -= time;
                         *priority += time;
                     }
                 }
             };
+            let piece = *piece;
+            if let Some(old_key) = old_key {
+                self.hash ^= old_key ^ piece_key(&piece);
+            }
+            if let Some((square, tick)) = arrival {
+                on_event(StepEvent::Arrived {
+                    piece,
+                    square,
+                    tick,
+                });
+                if is_promoted {
+                    on_event(StepEvent::Promoted {
+                        piece,
+                        square,
+                        tick,
+                    });
+                }
+            }
+            if let Some((square, tick)) = cooldown_expiry {
+                on_event(StepEvent::CooldownExpired {
+                    piece,
+                    square,
+                    tick,
+                });
+            }
         }
+        self.occupancy = Occupancy::new(&self.pieces);
     }
     pub fn get_all_possible_moves_naive(&self, side: Side) -> Vec<BoardMove> {
         let mut moves: Vec<BoardMove> = Vec::new();
         // Handle castling
-        if self.can_long_castle[side] {
-            // TODO: Check cooldown
-            moves.push(BoardMove::LongCastle(side));
-        }
-        if self.can_short_castle[side] {
-            // TODO: Check cooldown
-            moves.push(BoardMove::ShortCastle(side));
+        for board_move in [BoardMove::LongCastle(side), BoardMove::ShortCastle(side)] {
+            if self.can_move(&board_move) {
+                moves.push(board_move);
+            }
         }
         for piece in self.pieces.iter().cloned() {
             if piece.side == side {
@@ -572,9 +1028,15 @@ impl BoardState {
                 for i in 0..BOARD_SIZE {
                     for j in 0..BOARD_SIZE {
                         let target = (i, j).into();
-                        let board_move = BoardMove::Normal { piece, target };
-                        if self.can_move(&board_move) {
-                            moves.push(board_move);
+                        for promotion in self.get_promotion_choices(&piece, target) {
+                            let board_move = BoardMove::Normal {
+                                piece,
+                                target,
+                                promotion,
+                            };
+                            if self.can_move(&board_move) {
+                                moves.push(board_move);
+                            }
                         }
                     }
                 }
@@ -643,10 +1105,14 @@ impl BoardState {
         }
         if let PieceState::Stationary { position, cooldown } = piece.state && let PieceState::Stationary { position: capturer_position, cooldown: capturer_cooldown } = capturer.state && capturer_cooldown == 0 {
 
-            let delta = position - capturer_position;
-            let transit_time = delta.dist_linf();
+            let transit_time = self.config.travel_time(capturer.kind, capturer_position, position);
             if cooldown >= transit_time {
-                let board_move = BoardMove::Normal { piece: *capturer, target: position };
+                let promotion = self.get_promotion_choices(capturer, position)[0];
+                let board_move = BoardMove::Normal {
+                    piece: *capturer,
+                    target: position,
+                    promotion,
+                };
                 if self.can_move(&board_move) {
                     Some(board_move)
                 } else {
@@ -659,23 +1125,18 @@ impl BoardState {
             None
         }
     }
-    // TODO: Optimizable across multiple calls
-    fn is_target_of_capture(&self, position: &Position) -> bool {
-        self.pieces.iter().any(|piece| {
-            if let PieceState::Moving {
-                target: MoveTarget { target, .. },
-                ..
-            } = piece.state
-            {
-                &target == position
-            } else {
-                false
-            }
-        })
+    pub fn is_target_of_capture(&self, position: &Position) -> bool {
+        self.occupancy.all_reserved() & to_bitboard(*position) != 0
     }
     // TODO-someday: return nonempty list?
     pub fn get_all_possible_moves(&self, side: Side) -> Vec<BoardMove> {
         let mut moves: Vec<BoardMove> = Vec::new();
+        if self.can_castle(side, true) {
+            moves.push(BoardMove::LongCastle(side));
+        }
+        if self.can_castle(side, false) {
+            moves.push(BoardMove::ShortCastle(side));
+        }
         for piece in self.pieces.iter() {
             if piece.side == side {
                 // TODO-someday: should be looping through stationary pieces and filter by side
@@ -771,7 +1232,7 @@ impl BoardState {
     pub fn to_stationary_map_cooldowns(&self) -> String {
         self.to_stationary_map('.', |piece| {
             if let PieceState::Stationary { cooldown, .. } = piece.state {
-                if cooldown == 10 {
+                if cooldown >= 10 {
                     'X'
                 } else {
                     cooldown.to_string().chars().next().unwrap()
@@ -782,13 +1243,7 @@ impl BoardState {
         })
     }
     pub fn to_stationary_map_combo(&self) -> String {
-        self.to_stationary_map('.', |piece| {
-            let c: char = piece.kind.into();
-            match piece.side {
-                Side::White => c.to_ascii_uppercase(),
-                Side::Black => c.to_ascii_lowercase(),
-            }
-        })
+        self.to_stationary_map('.', |piece| piece_to_fen_char(piece.side, piece.kind))
     }
     pub fn to_stationary_map_type(&self) -> String {
         self.to_stationary_map('.', |piece| piece.kind.into())
@@ -799,19 +1254,33 @@ impl BoardState {
             Side::Black => 'B',
         })
     }
+    // Parses either a plain FEN piece placement (ex: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR")
+    // or the extended format written by to_fen:
+    //     <placement> <castling rights> <cooldowns> <moving pieces>
+    // castling rights are written like in FEN ("KQkq" or "-"), or with the files of the rooks
+    // (ex: "GBgb") when the king and rooks do not start on the usual files
+    // cooldowns are written as <square>:<cooldown>, separated by commas (or "-")
+    // moving pieces are written as <piece>:<x>:<y>:<target>:<turns left>:<priority>:<vx>:<vy>,
+    // separated by commas (or "-"), where (x, y) are board coordinates (y = 0 is the 8th rank)
+    // a moving pawn that has chosen a promotion gets an extra :<kind> at the end (ex: ":N")
     pub fn parse_fen(fen: &str) -> OrError<Self> {
+        fn parse_list(field: &str) -> impl Iterator<Item = &str> {
+            field.split(',').filter(|entry| *entry != "-")
+        }
+        fn parse_square(square: &str) -> OrError<Position> {
+            Position::from_square_name(square)
+                .ok_or_else(|| Error!("Unable to parse square {}", square))
+        }
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next().unwrap_or("");
         let mut pieces = Vec::new();
-        for (row, line) in fen.split('/').enumerate() {
+        for (row, line) in placement.split('/').enumerate() {
             let mut cursor = 0;
             for c in line.chars() {
                 if c.is_ascii_digit() {
                     cursor += c.to_digit(10).unwrap();
                 } else {
-                    if let Some(kind) = PieceKind::from_char(c.to_ascii_uppercase()) {
-                        let side = match c.is_ascii_uppercase() {
-                            true => Side::White,
-                            false => Side::Black,
-                        };
+                    if let Some((side, kind)) = piece_from_fen_char(c) {
                         let position = (cursor, row as u32).into();
                         pieces.push(Piece {
                             side,
@@ -828,40 +1297,264 @@ impl BoardState {
                 }
             }
         }
-        Ok(Self::new_with_castling(pieces, false))
-    }
-    pub fn to_stationary_fen(&self) -> OrError<String> {
-        if self.is_all_pieces_stationary() {
-            let mut fen = Vec::new();
-            for row in 0..BOARD_SIZE {
-                let mut s = String::new();
-                let mut empty = 0u32;
-                for column in 0..BOARD_SIZE {
-                    if let Some(piece) = self.get_stationary_piece((column, row).into()) {
-                        if empty > 0 {
-                            s.push_str(empty.to_string().as_str());
-                            empty = 0;
-                        }
-                        let c: char = piece.kind.into();
-                        if piece.side == Side::White {
-                            s.push(c.to_ascii_uppercase());
+        let mut can_long_castle = enum_map! { _ => false };
+        let mut can_short_castle = enum_map! { _ => false };
+        let mut castling_files = CastlingFiles::STANDARD;
+        if let Some(castling) = fields.next() && castling != "-" {
+            let error = || Error!("Unable to parse castling rights {}", castling);
+            // files given as letters (Shredder-FEN), which have to agree between both sides
+            let (mut king, mut long_rook, mut short_rook) = (None, None, None);
+            let set_file = |file: &mut Option<u32>, value: u32| match file.replace(value) {
+                Some(existing) if existing != value => Err(error()),
+                _ => Ok(()),
+            };
+            for c in castling.chars() {
+                match c {
+                    'K' => can_short_castle[Side::White] = true,
+                    'Q' => can_long_castle[Side::White] = true,
+                    'k' => can_short_castle[Side::Black] = true,
+                    'q' => can_long_castle[Side::Black] = true,
+                    'A'..='H' | 'a'..='h' => {
+                        let side = if c.is_ascii_uppercase() {
+                            Side::White
+                        } else {
+                            Side::Black
+                        };
+                        let rook_file = c.to_ascii_lowercase() as u32 - 'a' as u32;
+                        let king_file = pieces
+                            .iter()
+                            .find_map(|piece| match piece.state {
+                                PieceState::Stationary { position, .. }
+                                    if piece.side == side
+                                        && piece.kind == PieceKind::King
+                                        && position.y == back_rank_y(side) =>
+                                {
+                                    Some(position.x)
+                                }
+                                _ => None,
+                            })
+                            .ok_or_else(error)?;
+                        set_file(&mut king, king_file)?;
+                        if rook_file < king_file {
+                            can_long_castle[side] = true;
+                            set_file(&mut long_rook, rook_file)?;
+                        } else if rook_file > king_file {
+                            can_short_castle[side] = true;
+                            set_file(&mut short_rook, rook_file)?;
                         } else {
-                            s.push(c.to_ascii_lowercase());
+                            return Err(error());
                         }
-                    } else {
-                        empty += 1;
                     }
+                    _ => return Err(error()),
                 }
-                if empty > 0 {
-                    s.push_str(empty.to_string().as_str());
+            }
+            if let Some(king) = king {
+                castling_files = CastlingFiles {
+                    king,
+                    long_rook: long_rook.unwrap_or(CastlingFiles::STANDARD.long_rook),
+                    short_rook: short_rook.unwrap_or(CastlingFiles::STANDARD.short_rook),
+                };
+            }
+        }
+        if let Some(cooldowns) = fields.next() {
+            for entry in parse_list(cooldowns) {
+                let (square, cooldown) = entry
+                    .split_once(':')
+                    .ok_or_else(|| Error!("Unable to parse cooldown {}", entry))?;
+                let position = parse_square(square)?;
+                let cooldown = cooldown.parse::<u32>()?;
+                let piece_cooldown = pieces
+                    .iter_mut()
+                    .find_map(|piece| match &mut piece.state {
+                        PieceState::Stationary {
+                            position: piece_position,
+                            cooldown,
+                        } if *piece_position == position => Some(cooldown),
+                        _ => None,
+                    })
+                    .ok_or_else(|| Error!("No stationary piece for cooldown {}", entry))?;
+                *piece_cooldown = cooldown;
+            }
+        }
+        if let Some(moving) = fields.next() {
+            for entry in parse_list(moving) {
+                let mut values = entry.split(':').collect_vec();
+                // the promotion kind is only written when the move carries one
+                let promotion = if values.len() == 9 {
+                    let kind = values.pop().unwrap();
+                    let promotion = kind
+                        .parse::<char>()
+                        .ok()
+                        .and_then(PieceKind::from_char)
+                        .ok_or_else(|| Error!("Unable to parse promotion {} in {}", kind, entry))?;
+                    Some(promotion)
+                } else {
+                    None
+                };
+                if let [c, x, y, target, turns_left, priority, vx, vy] = values[..] {
+                    let (side, kind) = c
+                        .parse::<char>()
+                        .ok()
+                        .and_then(piece_from_fen_char)
+                        .ok_or_else(|| Error!("Unable to parse piece kind {} in {}", c, entry))?;
+                    pieces.push(Piece {
+                        side,
+                        kind,
+                        state: PieceState::Moving {
+                            x: x.parse()?,
+                            y: y.parse()?,
+                            target: MoveTarget {
+                                target: parse_square(target)?,
+                                turns_left: turns_left.parse()?,
+                                priority: priority.parse()?,
+                                velocity: (vx.parse()?, vy.parse()?),
+                                promotion,
+                            },
+                        },
+                    });
+                } else {
+                    return Err(Error!("Unable to parse moving piece {}", entry));
                 }
-                fen.push(s);
             }
-            Ok(fen.join("/"))
+        }
+        let config = match fields.next() {
+            Some(config) => GameConfig::parse_fen_field(config)?,
+            None => GameConfig::standard(),
+        };
+        if let Some(extra) = fields.next() {
+            return Err(Error!("Unexpected field {} in {}", extra, fen));
+        }
+        Ok(
+            Self::new(pieces, can_long_castle, can_short_castle, castling_files)
+                .with_config(config),
+        )
+    }
+    pub fn to_stationary_fen(&self) -> OrError<String> {
+        if self.is_all_pieces_stationary() {
+            Ok(self.to_fen_placement())
         } else {
             Err(Error!("Pieces are not all stationary"))
         }
     }
+    // lossless counterpart of parse_fen: unlike to_stationary_fen, this also records
+    // castling rights, cooldowns and moving pieces, and the config when it is not the standard one
+    pub fn to_fen(&self) -> String {
+        fn or_dash(s: String) -> String {
+            if s.is_empty() {
+                "-".to_owned()
+            } else {
+                s
+            }
+        }
+        let castling: String = [
+            (Side::White, false, 'K'),
+            (Side::White, true, 'Q'),
+            (Side::Black, false, 'k'),
+            (Side::Black, true, 'q'),
+        ]
+        .into_iter()
+        .filter(|&(side, is_long_castle, _)| {
+            if is_long_castle {
+                self.can_long_castle[side]
+            } else {
+                self.can_short_castle[side]
+            }
+        })
+        .map(|(side, is_long_castle, c)| {
+            if self.castling_files == CastlingFiles::STANDARD {
+                return c;
+            }
+            // Chess960 rights are written as the files of the rooks instead (Shredder-FEN)
+            let CastlingFiles {
+                long_rook,
+                short_rook,
+                ..
+            } = self.castling_files;
+            let file = (b'a' + if is_long_castle { long_rook } else { short_rook } as u8) as char;
+            match side {
+                Side::White => file.to_ascii_uppercase(),
+                Side::Black => file,
+            }
+        })
+        .collect();
+        // sorted so that the output does not depend on the order of self.pieces
+        let cooldowns = self
+            .pieces
+            .iter()
+            .filter_map(|piece| match piece.state {
+                PieceState::Stationary { position, cooldown } if cooldown > 0 => {
+                    Some((position.y, position.x, format!("{position}:{cooldown}")))
+                }
+                _ => None,
+            })
+            .sorted()
+            .map(|(_, _, entry)| entry)
+            .join(",");
+        let moving = self
+            .pieces
+            .iter()
+            .filter_map(|piece| match piece.state {
+                PieceState::Moving {
+                    x,
+                    y,
+                    target:
+                        MoveTarget {
+                            target,
+                            turns_left,
+                            priority,
+                            velocity: (vx, vy),
+                            promotion,
+                        },
+                } => Some((
+                    target.y,
+                    target.x,
+                    format!(
+                        "{}:{x}:{y}:{target}:{turns_left}:{priority}:{vx}:{vy}{}",
+                        piece_to_fen_char(piece.side, piece.kind),
+                        promotion.map_or(String::new(), |kind| format!(":{}", char::from(kind)))
+                    ),
+                )),
+                PieceState::Stationary { .. } => None,
+            })
+            .sorted()
+            .map(|(_, _, entry)| entry)
+            .join(",");
+        let fen = format!(
+            "{} {} {} {}",
+            self.to_fen_placement(),
+            or_dash(castling),
+            or_dash(cooldowns),
+            or_dash(moving)
+        );
+        match self.config.to_fen_field() {
+            Some(config) => format!("{fen} {config}"),
+            None => fen,
+        }
+    }
+    // only includes stationary pieces
+    fn to_fen_placement(&self) -> String {
+        let mut fen = Vec::new();
+        for row in 0..BOARD_SIZE {
+            let mut s = String::new();
+            let mut empty = 0u32;
+            for column in 0..BOARD_SIZE {
+                if let Some(piece) = self.get_stationary_piece((column, row).into()) {
+                    if empty > 0 {
+                        s.push_str(empty.to_string().as_str());
+                        empty = 0;
+                    }
+                    s.push(piece_to_fen_char(piece.side, piece.kind));
+                } else {
+                    empty += 1;
+                }
+            }
+            if empty > 0 {
+                s.push_str(empty.to_string().as_str());
+            }
+            fen.push(s);
+        }
+        fen.join("/")
+    }
     pub fn is_all_pieces_stationary(&self) -> bool {
         self.pieces.iter().all(|piece| piece.state.is_stationary())
     }
@@ -870,37 +1563,24 @@ impl BoardState {
             |piece| matches!(piece.state, PieceState::Stationary { cooldown, .. } if cooldown == 0),
         )
     }
-    fn has_overlapping_pieces(&self) -> bool {
-        self.pieces.iter().tuple_combinations().any(|(a, b)| {
-            if let PieceState::Stationary { position: a_position, .. } = a.state &&
-                let PieceState::Stationary { position: b_position, .. } = b.state {
-                a_position == b_position
-            } else {
-                false
-            }
-        })
-    }
     // whether destination is a valid destination for a piece of a side
     fn is_valid_destination(&self, side: Side, destination: Position) -> bool {
         // ensures the destination is not some other piece's target
         // or has an existing stationary piece
-        !self.pieces.iter().any(|piece| {
-            if side != piece.side {
-                return false;
-            }
-            match piece.state {
-                PieceState::Stationary {
-                    position: piece_position,
-                    ..
-                } => destination == piece_position,
-                PieceState::Moving {
-                    target: MoveTarget { target, .. },
-                    ..
-                } => destination == target,
-            }
-        })
+        (self.occupancy.stationary(side) | self.occupancy.reserved(side)) & to_bitboard(destination)
+            == 0
     }
-    pub fn generate_random_board_with(num_pieces_per_side: usize) -> Self {
+    pub fn generate_random_board_with_num_pieces(num_pieces_per_side: usize) -> Self {
+        Self::generate_random_board_with_num_pieces_rng(
+            &mut rand::thread_rng(),
+            num_pieces_per_side,
+        )
+    }
+    // same as generate_random_board_with_num_pieces, but reproducible given a seeded rng
+    pub fn generate_random_board_with_num_pieces_rng<R: Rng + ?Sized>(
+        rng: &mut R,
+        num_pieces_per_side: usize,
+    ) -> Self {
         let distribution = "PPPPPPPPNNBBRRQ"
             .chars()
             .map(|c| PieceKind::from_char(c).unwrap())
@@ -909,29 +1589,36 @@ impl BoardState {
         debug_assert!(num_pieces_per_side <= distribution.len());
 
         let white_pieces = distribution
-            .choose_multiple(&mut rand::thread_rng(), num_pieces_per_side - 1)
+            .choose_multiple(rng, num_pieces_per_side - 1)
             .chain(std::iter::once(&PieceKind::King))
             .map(|kind| (Side::White, *kind));
         let black_pieces = distribution
-            .choose_multiple(&mut rand::thread_rng(), num_pieces_per_side - 1)
+            .choose_multiple(rng, num_pieces_per_side - 1)
             .chain(std::iter::once(&PieceKind::King))
             .map(|kind| (Side::Black, *kind));
         let pieces = white_pieces.chain(black_pieces).collect_vec();
-        Self::generate_random_board(pieces)
+        Self::generate_random_board_rng(rng, pieces)
     }
-    // TODO-someday: maybe put some rules that will generate reasonable boards
     pub fn generate_random_board(pieces: impl IntoIterator<Item = (Side, PieceKind)>) -> Self {
-        fn random_position() -> Position {
-            let x = rand::thread_rng().gen_range(0..BOARD_SIZE) as u32;
-            let y = rand::thread_rng().gen_range(0..BOARD_SIZE) as u32;
+        Self::generate_random_board_rng(&mut rand::thread_rng(), pieces)
+    }
+    // places pieces uniformly, see generate_constrained_board for boards that look like real games
+    pub fn generate_random_board_rng<R: Rng + ?Sized>(
+        rng: &mut R,
+        pieces: impl IntoIterator<Item = (Side, PieceKind)>,
+    ) -> Self {
+        fn random_position<R: Rng + ?Sized>(rng: &mut R) -> Position {
+            let x = rng.gen_range(0..BOARD_SIZE) as u32;
+            let y = rng.gen_range(0..BOARD_SIZE) as u32;
             Position { x, y }
         }
-        fn random_with_reqs<F>(f: F) -> Position
+        fn random_with_reqs<R, F>(rng: &mut R, f: F) -> Position
         where
+            R: Rng + ?Sized,
             F: Fn(&Position) -> bool,
         {
             loop {
-                let position = random_position();
+                let position = random_position(rng);
                 if f(&position) {
                     return position;
                 }
@@ -940,19 +1627,20 @@ impl BoardState {
         let mut pieces_vec = Vec::new();
         let mut occupied = [[false; BOARD_SIZE]; BOARD_SIZE];
 
-        fn add_piece(
+        fn add_piece<R: Rng + ?Sized>(
+            rng: &mut R,
             pieces: &mut Vec<Piece>,
             occupied: &mut [[bool; BOARD_SIZE]; BOARD_SIZE],
             side: Side,
             kind: PieceKind,
         ) {
             let position = match kind {
-                PieceKind::Pawn => random_with_reqs(|pos| {
+                PieceKind::Pawn => random_with_reqs(rng, |pos| {
                     pos.y != 0
                         && pos.y != BOARD_SIZE as u32 - 1
                         && !occupied[pos.x as usize][pos.y as usize]
                 }),
-                _ => random_with_reqs(|pos| !occupied[pos.x as usize][pos.y as usize]),
+                _ => random_with_reqs(rng, |pos| !occupied[pos.x as usize][pos.y as usize]),
             };
             occupied[position.x as usize][position.y as usize] = true;
             pieces.push(Piece {
@@ -965,18 +1653,88 @@ impl BoardState {
             });
         }
         for (side, kind) in pieces {
-            add_piece(&mut pieces_vec, &mut occupied, side, kind);
+            add_piece(rng, &mut pieces_vec, &mut occupied, side, kind);
         }
         Self::new_with_castling(pieces_vec, false)
     }
 }
 
+pub(super) fn piece_to_fen_char(side: Side, kind: PieceKind) -> char {
+    let c: char = kind.into();
+    match side {
+        Side::White => c.to_ascii_uppercase(),
+        Side::Black => c.to_ascii_lowercase(),
+    }
+}
+
+fn piece_from_fen_char(c: char) -> Option<(Side, PieceKind)> {
+    let kind = PieceKind::from_char(c.to_ascii_uppercase())?;
+    let side = match c.is_ascii_uppercase() {
+        true => Side::White,
+        false => Side::Black,
+    };
+    Some((side, kind))
+}
+
+// rebuilds the occupancy index once the caller is done modifying the pieces
+pub struct PiecesMut<'a> {
+    board: &'a mut BoardState,
+}
+
+impl Deref for PiecesMut<'_> {
+    type Target = Vec<Piece>;
+    fn deref(&self) -> &Vec<Piece> {
+        &self.board.pieces
+    }
+}
+
+impl DerefMut for PiecesMut<'_> {
+    fn deref_mut(&mut self) -> &mut Vec<Piece> {
+        &mut self.board.pieces
+    }
+}
+
+impl Drop for PiecesMut<'_> {
+    fn drop(&mut self) {
+        self.board.reindex();
+    }
+}
+
+// Boards are equal when they have the same pieces (in any order) and castling rights
+// (and castling files, as long as there is a right to use them);
+// the config is not compared
+impl PartialEq for BoardState {
+    fn eq(&self, other: &Self) -> bool {
+        let sorted_piece_values =
+            |board: &BoardState| board.pieces.iter().map(piece_values).sorted().collect_vec();
+        // the hash covers everything else, so it rules out almost all unequal boards cheaply
+        self.hash == other.hash
+            && self.can_long_castle == other.can_long_castle
+            && self.can_short_castle == other.can_short_castle
+            && (self.castling_files == other.castling_files || !self.has_any_castling_right())
+            && sorted_piece_values(self) == sorted_piece_values(other)
+    }
+}
+
+impl Eq for BoardState {}
+
+impl Hash for BoardState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum BoardMove {
     None(Side),
     LongCastle(Side),
     ShortCastle(Side),
-    Normal { piece: Piece, target: Position }, // TODO-someday: PieceState should always be stationary here - represent this differently?
+    // TODO-someday: PieceState should always be stationary here - represent this differently?
+    Normal {
+        piece: Piece,
+        target: Position,
+        promotion: Option<PieceKind>, // only for pawns moving to the last rank
+    },
 }
 
 impl BoardMove {
@@ -990,6 +1748,51 @@ impl BoardMove {
     }
 }
 
+// The files the king and the castling rooks start on, the same for both sides.
+// Like in Chess960, castling always ends with the king and the rook on the usual squares
+// (c and d for a long castle, g and f for a short castle) wherever they start
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CastlingFiles {
+    pub king: u32,
+    pub long_rook: u32,
+    pub short_rook: u32,
+}
+
+impl CastlingFiles {
+    pub const STANDARD: Self = Self {
+        king: 4,
+        long_rook: 0,
+        short_rook: BOARD_SIZE as u32 - 1,
+    };
+}
+
+struct CastlingPositions {
+    king_start: Position,
+    king_end: Position,
+    rook_start: Position,
+    rook_end: Position,
+}
+
+impl CastlingPositions {
+    fn new(side: Side, is_long_castle: bool, files: &CastlingFiles) -> Self {
+        let y = match side {
+            Side::White => BOARD_SIZE as u32 - 1,
+            Side::Black => 0,
+        };
+        let (king_end_x, rook_start_x, rook_end_x) = if is_long_castle {
+            (2, files.long_rook, 3)
+        } else {
+            (6, files.short_rook, 5)
+        };
+        Self {
+            king_start: (files.king, y).into(),
+            king_end: (king_end_x, y).into(),
+            rook_start: (rook_start_x, y).into(),
+            rook_end: (rook_end_x, y).into(),
+        }
+    }
+}
+
 struct MoveGenerator<'a> {
     piece: &'a Piece,
     position: Position,
@@ -1016,19 +1819,20 @@ impl MoveGenerator<'_> {
     }
     fn is_valid_force_no_capture_destination(&self, destination: Position) -> bool {
         // used by pawns only
-        self.is_valid_destination(destination)
-            && self.board.get_stationary_piece(destination).is_none()
+        self.is_valid_destination(destination) && !self.board.has_stationary_piece(destination)
     }
     fn is_valid_force_capture_destination(&self, destination: Position) -> bool {
         // used by pawns only
-        self.is_valid_destination(destination)
-            && self.board.get_stationary_piece(destination).is_some()
+        self.is_valid_destination(destination) && self.board.has_stationary_piece(destination)
     }
     fn add_board_move(&mut self, target: Position) {
-        self.moves.push(BoardMove::Normal {
-            piece: *self.piece,
-            target,
-        });
+        for promotion in self.board.get_promotion_choices(self.piece, target) {
+            self.moves.push(BoardMove::Normal {
+                piece: *self.piece,
+                target,
+                promotion,
+            });
+        }
     }
     // knights, kings
     fn add_moves_by_deltas<const N: usize>(&mut self, deltas: [Delta; N]) {
@@ -1050,8 +1854,7 @@ impl MoveGenerator<'_> {
                     // break when it's an enemy
                     let has_enemy = self
                         .board
-                        .get_stationary_piece(current)
-                        .map_or(false, |piece| piece.side != self.side);
+                        .has_stationary_piece_on_side(current, self.side.opposite());
                     if has_enemy {
                         break;
                     }
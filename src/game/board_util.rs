@@ -9,6 +9,19 @@ pub fn forward_y(side: Side) -> i32 {
     }
 }
 
+// the rank on which the side's pawns are promoted
+pub fn promotion_y(side: Side) -> u32 {
+    match side {
+        Side::White => 0u32,
+        Side::Black => BOARD_SIZE as u32 - 1u32,
+    }
+}
+
+// the rank on which the side's pieces other than pawns start
+pub fn back_rank_y(side: Side) -> u32 {
+    promotion_y(side.opposite())
+}
+
 pub fn to_char_map<F>(func: F) -> String
 where
     F: Fn(Position) -> char,
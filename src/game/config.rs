@@ -0,0 +1,136 @@
+core!();
+
+use super::*;
+use enum_map::EnumMap;
+use itertools::Itertools;
+
+// Rules that can vary between Kung Fu Chess variants
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GameConfig {
+    pub piece_cooldown: u32, // number of turns a piece has to wait after arriving
+    pub ticks_per_square: EnumMap<PieceKind, u32>, // must be at least 1
+    pub promotion: PromotionRule,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PromotionRule {
+    Never,             // pawns stay on the last rank
+    Always(PieceKind), // pawns turn into the given kind on arrival
+    Choice,            // the pawn move picks one of PROMOTION_KINDS
+}
+
+// ordered from most to least valuable so that search looks at the queen first
+pub const PROMOTION_KINDS: [PieceKind; 4] = [
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+];
+
+impl GameConfig {
+    pub fn standard() -> Self {
+        Self {
+            piece_cooldown: PIECE_COOLDOWN,
+            ticks_per_square: enum_map! { _ => 1 },
+            promotion: PromotionRule::Choice,
+        }
+    }
+    // pieces can move again almost immediately after arriving
+    pub fn lightning() -> Self {
+        Self {
+            piece_cooldown: 2,
+            ..Self::standard()
+        }
+    }
+    // pieces take twice as long to travel, so moves are easier to dodge
+    pub fn slow() -> Self {
+        Self {
+            ticks_per_square: enum_map! { _ => 2 },
+            ..Self::standard()
+        }
+    }
+    pub fn with_piece_cooldown(self, piece_cooldown: u32) -> Self {
+        Self {
+            piece_cooldown,
+            ..self
+        }
+    }
+    pub fn with_ticks_per_square(mut self, kind: PieceKind, ticks: u32) -> Self {
+        debug_assert!(ticks >= 1);
+        self.ticks_per_square[kind] = ticks;
+        self
+    }
+    pub fn with_promotion(self, promotion: PromotionRule) -> Self {
+        Self { promotion, ..self }
+    }
+    // promotion kinds a pawn move to the last rank can carry (None means no choice is made)
+    pub fn promotion_choices(&self) -> Vec<Option<PieceKind>> {
+        match self.promotion {
+            PromotionRule::Choice => PROMOTION_KINDS.into_iter().map(Some).collect(),
+            PromotionRule::Never | PromotionRule::Always(_) => vec![None],
+        }
+    }
+    // number of turns for a piece of the given kind to travel from start to end
+    pub fn travel_time(&self, kind: PieceKind, start: Position, end: Position) -> u32 {
+        (end - start).dist_linf() * self.ticks_per_square[kind]
+    }
+    // the extended FEN field for the rules that differ from the standard ones, like
+    // "cooldown:2,ticks:N:3,promotion:never", or None for the standard config
+    pub fn to_fen_field(&self) -> Option<String> {
+        let standard = Self::standard();
+        let mut entries = Vec::new();
+        if self.piece_cooldown != standard.piece_cooldown {
+            entries.push(format!("cooldown:{}", self.piece_cooldown));
+        }
+        for (kind, ticks) in self.ticks_per_square {
+            if ticks != standard.ticks_per_square[kind] {
+                entries.push(format!("ticks:{}:{ticks}", char::from(kind)));
+            }
+        }
+        if self.promotion != standard.promotion {
+            let promotion = match self.promotion {
+                PromotionRule::Never => "never".to_owned(),
+                PromotionRule::Always(kind) => char::from(kind).to_string(),
+                PromotionRule::Choice => "choice".to_owned(),
+            };
+            entries.push(format!("promotion:{promotion}"));
+        }
+        if entries.is_empty() {
+            None
+        } else {
+            Some(entries.join(","))
+        }
+    }
+    pub fn parse_fen_field(field: &str) -> OrError<Self> {
+        let parse_kind = |kind: &str| {
+            kind.parse::<char>()
+                .ok()
+                .and_then(PieceKind::from_char)
+                .ok_or_else(|| Error!("Unable to parse piece kind {} in {}", kind, field))
+        };
+        let mut config = Self::standard();
+        for entry in field.split(',').filter(|entry| *entry != "-") {
+            match entry.split(':').collect_vec()[..] {
+                ["cooldown", cooldown] => config.piece_cooldown = cooldown.parse()?,
+                ["ticks", kind, ticks] => {
+                    let ticks = ticks.parse()?;
+                    if ticks == 0 {
+                        return Err(Error!("Ticks per square must be at least 1 in {}", entry));
+                    }
+                    config.ticks_per_square[parse_kind(kind)?] = ticks;
+                }
+                ["promotion", "never"] => config.promotion = PromotionRule::Never,
+                ["promotion", "choice"] => config.promotion = PromotionRule::Choice,
+                ["promotion", kind] => config.promotion = PromotionRule::Always(parse_kind(kind)?),
+                _ => return Err(Error!("Unable to parse config {}", entry)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
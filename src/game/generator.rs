@@ -0,0 +1,282 @@
+core!();
+
+use std::ops::RangeInclusive;
+
+use super::*;
+use itertools::Itertools;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    seq::SliceRandom,
+    Rng,
+};
+
+// Rules for generate_constrained_board, so that generated positions look like they come from
+// the middle of a real game instead of having pieces scattered uniformly
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionConstraints {
+    pub piece_counts: Vec<(usize, u32)>, // (pieces per side including the king, weight)
+    pub material_balance: RangeInclusive<i32>, // white minus black material, kings excluded
+    pub max_pawn_advance: u32,           // number of ranks a pawn can be ahead of its starting rank
+    pub cooldown_chance: f64,            // chance for each stationary piece to still be on cooldown
+    pub moving_chance: f64,              // chance for each piece (except kings) to be in flight
+    pub config: GameConfig,
+}
+
+// the pieces a side starts with, minus the king
+const ARMY: &str = "PPPPPPPPNNBBRRQ";
+const MAX_ATTEMPTS: usize = 10000;
+
+impl PositionConstraints {
+    pub fn midgame() -> Self {
+        Self {
+            piece_counts: vec![
+                (6, 1),
+                (7, 2),
+                (8, 3),
+                (9, 4),
+                (10, 4),
+                (11, 4),
+                (12, 3),
+                (13, 2),
+                (14, 1),
+            ],
+            material_balance: -3..=3,
+            max_pawn_advance: 3,
+            cooldown_chance: 0.2,
+            moving_chance: 0.1,
+            config: GameConfig::standard(),
+        }
+    }
+    pub fn with_piece_counts(self, piece_counts: Vec<(usize, u32)>) -> Self {
+        debug_assert!(piece_counts
+            .iter()
+            .all(|&(count, _)| (1..=ARMY.len() + 1).contains(&count)));
+        Self {
+            piece_counts,
+            ..self
+        }
+    }
+    pub fn with_material_balance(self, material_balance: RangeInclusive<i32>) -> Self {
+        Self {
+            material_balance,
+            ..self
+        }
+    }
+    pub fn with_max_pawn_advance(self, max_pawn_advance: u32) -> Self {
+        // pawns on the last rank would already have been promoted
+        debug_assert!(max_pawn_advance <= BOARD_SIZE as u32 - 3);
+        Self {
+            max_pawn_advance,
+            ..self
+        }
+    }
+    pub fn with_cooldown_chance(self, cooldown_chance: f64) -> Self {
+        Self {
+            cooldown_chance,
+            ..self
+        }
+    }
+    pub fn with_moving_chance(self, moving_chance: f64) -> Self {
+        Self {
+            moving_chance,
+            ..self
+        }
+    }
+    pub fn with_config(self, config: GameConfig) -> Self {
+        Self { config, ..self }
+    }
+    // whether a board generated with these constraints is acceptable
+    pub fn is_satisfied_by(&self, board: &BoardState) -> bool {
+        let material_balance: i32 = board
+            .pieces()
+            .iter()
+            .filter(|piece| piece.kind != PieceKind::King)
+            .map(|piece| match piece.side {
+                Side::White => MATERIAL_VALUE[piece.kind] as i32,
+                Side::Black => -(MATERIAL_VALUE[piece.kind] as i32),
+            })
+            .sum();
+        let kings = board
+            .pieces()
+            .iter()
+            .filter_map(|piece| match piece.state {
+                PieceState::Stationary { position, .. } if piece.kind == PieceKind::King => {
+                    Some(position)
+                }
+                _ => None,
+            })
+            .collect_vec();
+        let kings_are_apart = match kings[..] {
+            [a, b] => (b - a).dist_linf() > 1,
+            _ => false,
+        };
+        self.material_balance.contains(&material_balance)
+            && kings_are_apart
+            && !has_capture_on_first_tick(board)
+    }
+}
+
+impl Default for PositionConstraints {
+    fn default() -> Self {
+        Self::midgame()
+    }
+}
+
+// whether a piece could be taken during the first tick, either by a piece that is already in
+// flight or by a move that only takes a single tick
+fn has_capture_on_first_tick(board: &BoardState) -> bool {
+    let mut stepped = board.clone();
+    let events =
+        stepped.step_with_events(&BoardMove::None(Side::White), &BoardMove::None(Side::Black));
+    if events
+        .iter()
+        .any(|event| matches!(event, StepEvent::Captured { .. }))
+    {
+        return true;
+    }
+    [Side::White, Side::Black].into_iter().any(|side| {
+        board
+            .get_all_possible_moves(side)
+            .iter()
+            .any(|board_move| match board_move {
+                BoardMove::Normal { piece, target, .. } => {
+                    let PieceState::Stationary { position, .. } = piece.state else {
+                        return false;
+                    };
+                    let is_capture = board
+                        .pieces()
+                        .iter()
+                        .any(|other| other.side != side && is_stationary_on(other, *target));
+                    is_capture && board.config().travel_time(piece.kind, position, *target) <= 1
+                }
+                _ => false,
+            })
+    })
+}
+
+impl BoardState {
+    pub fn generate_constrained_board(constraints: &PositionConstraints) -> OrError<Self> {
+        Self::generate_constrained_board_rng(&mut rand::thread_rng(), constraints)
+    }
+    // retries random placements until one satisfies the constraints
+    pub fn generate_constrained_board_rng<R: Rng + ?Sized>(
+        rng: &mut R,
+        constraints: &PositionConstraints,
+    ) -> OrError<Self> {
+        let weights = constraints
+            .piece_counts
+            .iter()
+            .map(|&(_, weight)| weight)
+            .collect_vec();
+        let piece_count_distribution = WeightedIndex::new(weights)?;
+        let army = ARMY
+            .chars()
+            .map(|c| PieceKind::from_char(c).unwrap())
+            .collect_vec();
+        for _ in 0..MAX_ATTEMPTS {
+            let mut pieces = Vec::new();
+            for side in [Side::White, Side::Black] {
+                let (num_pieces, _) =
+                    constraints.piece_counts[piece_count_distribution.sample(rng)];
+                let kinds = std::iter::once(PieceKind::King)
+                    .chain(army.choose_multiple(rng, num_pieces - 1).copied())
+                    .collect_vec();
+                for kind in kinds {
+                    let position = random_square(rng, &pieces, side, kind, constraints)?;
+                    let cooldown = if rng.gen_bool(constraints.cooldown_chance) {
+                        rng.gen_range(1..=constraints.config.piece_cooldown.max(1))
+                    } else {
+                        0
+                    };
+                    pieces.push(Piece {
+                        side,
+                        kind,
+                        state: PieceState::Stationary { position, cooldown },
+                    });
+                }
+            }
+            let mut board = Self::new_with_castling(pieces, false).with_config(constraints.config);
+            start_random_moves(rng, &mut board, constraints.moving_chance);
+            if constraints.is_satisfied_by(&board) {
+                return Ok(board);
+            }
+        }
+        Err(Error!(
+            "Unable to generate a board satisfying {:?} after {} attempts",
+            constraints,
+            MAX_ATTEMPTS
+        ))
+    }
+}
+
+fn is_stationary_on(piece: &Piece, square: Position) -> bool {
+    matches!(piece.state, PieceState::Stationary { position, .. } if position == square)
+}
+
+// a free square for the piece, with pawns kept close to their starting rank
+// and kings kept away from each other
+fn random_square<R: Rng + ?Sized>(
+    rng: &mut R,
+    pieces: &[Piece],
+    side: Side,
+    kind: PieceKind,
+    constraints: &PositionConstraints,
+) -> OrError<Position> {
+    let occupied = |square: Position| pieces.iter().any(|piece| is_stationary_on(piece, square));
+    let next_to_enemy_king = |square: Position| {
+        pieces.iter().any(|piece| match piece.state {
+            PieceState::Stationary { position, .. } => {
+                piece.side != side
+                    && piece.kind == PieceKind::King
+                    && (position - square).dist_linf() <= 1
+            }
+            PieceState::Moving { .. } => false,
+        })
+    };
+    let starting_y = match side {
+        Side::White => BOARD_SIZE as i32 - 2,
+        Side::Black => 1,
+    };
+    let allowed = (0..BOARD_SIZE as u32)
+        .cartesian_product(0..BOARD_SIZE as u32)
+        .map(Position::from)
+        .filter(|&square| !occupied(square))
+        .filter(|&square| match kind {
+            PieceKind::Pawn => {
+                let advance = (square.y as i32 - starting_y) * forward_y(side);
+                (0..=constraints.max_pawn_advance as i32).contains(&advance)
+            }
+            PieceKind::King => !next_to_enemy_king(square),
+            _ => true,
+        })
+        .collect_vec();
+    allowed
+        .choose(rng)
+        .copied()
+        .ok_or_else(|| Error!("No square left for {:?} {:?}", side, kind))
+}
+
+// puts some of the pieces in flight, a tick into their move
+fn start_random_moves<R: Rng + ?Sized>(rng: &mut R, board: &mut BoardState, moving_chance: f64) {
+    if moving_chance <= 0.0 {
+        return;
+    }
+    let mut started_any = false;
+    for side in [Side::White, Side::Black] {
+        let pieces = board.pieces().clone();
+        for piece in pieces.iter().filter(|piece| piece.side == side) {
+            if piece.kind == PieceKind::King || !rng.gen_bool(moving_chance) {
+                continue;
+            }
+            let mut moves = Vec::new();
+            board.add_possible_moves_for_piece(piece, &mut moves);
+            if let Some(board_move) = moves.choose(rng) {
+                board.apply_move(board_move);
+                started_any = true;
+            }
+        }
+    }
+    if started_any {
+        board.step_without_moves();
+    }
+}
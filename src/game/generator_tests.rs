@@ -0,0 +1,78 @@
+core!();
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::*;
+
+#[test]
+fn test_generate_constrained_board() {
+    let constraints = PositionConstraints::midgame();
+    let board =
+        BoardState::generate_constrained_board_rng(&mut StdRng::seed_from_u64(3), &constraints)
+            .unwrap();
+    expect!(
+        board.to_fen(),
+        r#""5r2/1b1p2RN/4p3/6rp/bPP1P3/1k3B2/7P/4K1n1 - h7:6,b3:2 R:5:3:f4:1:1:0:1""#
+    );
+    // the same seed always gives the same board
+    let same_board =
+        BoardState::generate_constrained_board_rng(&mut StdRng::seed_from_u64(3), &constraints)
+            .unwrap();
+    assert_eq!(same_board, board);
+}
+
+fn generate_boards(constraints: &PositionConstraints, seed: u64) -> Vec<BoardState> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..50)
+        .map(|_| BoardState::generate_constrained_board_rng(&mut rng, constraints).unwrap())
+        .collect_vec()
+}
+
+#[test]
+fn test_constrained_boards_satisfy_constraints() {
+    let constraints = PositionConstraints::midgame().with_moving_chance(0.0);
+    for board in generate_boards(&constraints, 11).iter() {
+        assert!(constraints.is_satisfied_by(board));
+        for side in [Side::White, Side::Black] {
+            let num_pieces = board.pieces().iter().filter(|piece| piece.side == side).count();
+            assert!((6..=14).contains(&num_pieces));
+        }
+        for piece in board.pieces() {
+            if piece.kind == PieceKind::Pawn
+                && let PieceState::Stationary { position, .. } = piece.state
+            {
+                let rank = BOARD_SIZE as u32 - position.y;
+                match piece.side {
+                    Side::White => assert!((2..=5).contains(&rank)),
+                    Side::Black => assert!((4..=7).contains(&rank)),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_constrained_boards_with_cooldowns_and_moving_pieces() {
+    let constraints = PositionConstraints::midgame().with_moving_chance(0.3);
+    let boards = generate_boards(&constraints, 12);
+    assert!(boards.iter().all(|board| constraints.is_satisfied_by(board)));
+    let has_state = |predicate: fn(&PieceState) -> bool| {
+        boards
+            .iter()
+            .any(|board| board.pieces().iter().any(|piece| predicate(&piece.state)))
+    };
+    assert!(has_state(PieceState::is_moving));
+    assert!(has_state(|state| {
+        matches!(state, PieceState::Stationary { cooldown, .. } if *cooldown > 0)
+    }));
+}
+
+#[test]
+fn test_impossible_constraints() {
+    let constraints = PositionConstraints::midgame()
+        .with_piece_counts(vec![(1, 1)])
+        .with_material_balance(1..=3);
+    let mut rng = StdRng::seed_from_u64(0);
+    assert!(BoardState::generate_constrained_board_rng(&mut rng, &constraints).is_err());
+}
@@ -0,0 +1,179 @@
+core!();
+
+use super::*;
+use itertools::Itertools;
+use thiserror::Error;
+
+// how far a moving piece can drift from where its velocity says it should be, since positions
+// are advanced one f32 addition per tick
+const MOVING_POSITION_TOLERANCE: f32 = 1e-3;
+
+// Something that can never be true of a board reached by playing moves, found by
+// BoardState::validate
+#[derive(Debug, Copy, Clone, PartialEq, Error)]
+pub enum InvariantViolation {
+    #[error("the {first:?} and the {second:?} are both on {square}")]
+    OverlappingPieces {
+        first: PieceKind,
+        second: PieceKind,
+        square: Position,
+    },
+    #[error("the {side:?} {first:?} and {second:?} are both moving to {square}")]
+    SharedTarget {
+        side: Side,
+        first: PieceKind,
+        second: PieceKind,
+        square: Position,
+    },
+    #[error("the {side:?} {kind:?} at ({x}, {y}) is not on the board")]
+    OffBoard {
+        side: Side,
+        kind: PieceKind,
+        x: f32,
+        y: f32,
+    },
+    #[error("the {side:?} {kind:?} at ({x}, {y}) does not reach {target} in {turns_left} turns")]
+    VelocityMismatch {
+        side: Side,
+        kind: PieceKind,
+        x: f32,
+        y: f32,
+        target: Position,
+        turns_left: u32,
+    },
+    #[error("a {side:?} pawn is stuck on {square}")]
+    PawnOnBackRank { side: Side, square: Position },
+    #[error("{side:?} has {num_kings} kings")]
+    MultipleKings { side: Side, num_kings: usize },
+    #[error("the {kind:?} on {square} has a cooldown of {cooldown}, more than {max_cooldown}")]
+    CooldownTooLong {
+        kind: PieceKind,
+        square: Position,
+        cooldown: u32,
+        max_cooldown: u32,
+    },
+}
+
+impl BoardState {
+    // every invariant the board breaks, so that a broken step can be diagnosed in one go
+    pub fn validate(&self) -> Result<(), Vec<InvariantViolation>> {
+        let mut violations = Vec::new();
+        let is_on_board = |x: f32, y: f32| {
+            let range =
+                -MOVING_POSITION_TOLERANCE..=(BOARD_SIZE - 1) as f32 + MOVING_POSITION_TOLERANCE;
+            range.contains(&x) && range.contains(&y)
+        };
+        let mut stationary = [[None::<&Piece>; BOARD_SIZE]; BOARD_SIZE];
+        let mut reserved = enum_map! { _ => [[None::<&Piece>; BOARD_SIZE]; BOARD_SIZE] };
+        for piece in self.pieces() {
+            let Piece { side, kind, .. } = *piece;
+            match piece.state {
+                PieceState::Stationary { position, cooldown } => {
+                    if !is_on_board(position.x as f32, position.y as f32) {
+                        violations.push(InvariantViolation::OffBoard {
+                            side,
+                            kind,
+                            x: position.x as f32,
+                            y: position.y as f32,
+                        });
+                        continue;
+                    }
+                    let square = &mut stationary[position.y as usize][position.x as usize];
+                    if let Some(other) = square {
+                        violations.push(InvariantViolation::OverlappingPieces {
+                            first: other.kind,
+                            second: kind,
+                            square: position,
+                        });
+                    }
+                    *square = Some(piece);
+                    let is_pawn_stuck = position.y == back_rank_y(side)
+                        || (position.y == promotion_y(side)
+                            && self.config().promotion != PromotionRule::Never);
+                    if kind == PieceKind::Pawn && is_pawn_stuck {
+                        violations.push(InvariantViolation::PawnOnBackRank {
+                            side,
+                            square: position,
+                        });
+                    }
+                    let max_cooldown = self.config().piece_cooldown;
+                    if cooldown > max_cooldown {
+                        violations.push(InvariantViolation::CooldownTooLong {
+                            kind,
+                            square: position,
+                            cooldown,
+                            max_cooldown,
+                        });
+                    }
+                }
+                PieceState::Moving { x, y, target } => {
+                    let MoveTarget {
+                        target: square,
+                        turns_left,
+                        velocity: (vx, vy),
+                        ..
+                    } = target;
+                    if !is_on_board(x, y) || !is_on_board(square.x as f32, square.y as f32) {
+                        violations.push(InvariantViolation::OffBoard { side, kind, x, y });
+                        continue;
+                    }
+                    let arrival = (x + vx * turns_left as f32, y + vy * turns_left as f32);
+                    let is_off_target = |arrival: f32, target: u32| {
+                        (arrival - target as f32).abs() > MOVING_POSITION_TOLERANCE
+                    };
+                    if turns_left == 0
+                        || is_off_target(arrival.0, square.x)
+                        || is_off_target(arrival.1, square.y)
+                    {
+                        violations.push(InvariantViolation::VelocityMismatch {
+                            side,
+                            kind,
+                            x,
+                            y,
+                            target: square,
+                            turns_left,
+                        });
+                    }
+                    let reservation = &mut reserved[side][square.y as usize][square.x as usize];
+                    if let Some(other) = reservation {
+                        violations.push(InvariantViolation::SharedTarget {
+                            side,
+                            first: other.kind,
+                            second: kind,
+                            square,
+                        });
+                    }
+                    *reservation = Some(piece);
+                }
+            }
+        }
+        for side in [Side::White, Side::Black] {
+            let num_kings = self
+                .pieces()
+                .iter()
+                .filter(|piece| piece.side == side && piece.kind == PieceKind::King)
+                .count();
+            if num_kings > 1 {
+                violations.push(InvariantViolation::MultipleKings { side, num_kings });
+            }
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    // panics with every violation in debug builds, and does nothing in release builds
+    pub(super) fn debug_validate(&self) {
+        if cfg!(debug_assertions)
+            && let Err(violations) = self.validate()
+        {
+            panic!(
+                "invalid board {}:\n{}",
+                self.to_fen(),
+                violations.iter().join("\n")
+            );
+        }
+    }
+}
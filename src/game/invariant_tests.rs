@@ -0,0 +1,136 @@
+core!();
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use super::*;
+
+fn violations(board: &BoardState) -> Vec<String> {
+    match board.validate() {
+        Ok(()) => Vec::new(),
+        Err(violations) => violations.iter().map(ToString::to_string).collect(),
+    }
+}
+
+#[test]
+fn test_valid_boards() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+        "4k3/8/8/8/8/8/8/R2QK3 - d1:3 R:7:6:h4:2:0:0:-1",
+    ];
+    for fen in fens {
+        let board = BoardState::parse_fen(fen).unwrap();
+        assert!(board.validate().is_ok(), "{fen}");
+    }
+    // pawns stay on the last rank when they can not promote
+    let board = BoardState::parse_fen("P3k3/8/8/8/8/8/8/4K3")
+        .unwrap()
+        .with_config(GameConfig::standard().with_promotion(PromotionRule::Never));
+    assert!(board.validate().is_ok());
+}
+
+#[test]
+fn test_invalid_boards() {
+    let fens = [
+        "P3k3/8/8/8/8/8/8/4K2p",
+        "4k3/8/8/8/8/8/8/K3K3",
+        "4k3/8/8/8/8/8/8/4K3 - e1:11",
+        "4k3/8/8/8/8/8/8/4K3 - - R:7:6:h4:2:0:0:1",
+        "4k3/8/8/8/8/8/8/4K3 - - R:7:6:h4:2:0:0:-1,Q:5:6:h4:2:0:1:-1",
+        "4k3/8/8/8/8/8/8/4K3 - - N:8.5:6:h4:1:0:0:-1",
+    ];
+    let mut all_violations = fens
+        .iter()
+        .map(|fen| {
+            (
+                fen.to_string(),
+                violations(&BoardState::parse_fen(fen).unwrap()),
+            )
+        })
+        .collect::<Vec<_>>();
+    // parse_fen can not put two pieces on the same square
+    let mut board = BoardState::parse_fen("4k3/8/8/8/8/8/8/4K3").unwrap();
+    let king = board.pieces()[0];
+    board.pieces_mut().push(king);
+    all_violations.push(("overlapping kings".to_owned(), violations(&board)));
+    expect!(
+        all_violations,
+        r#"
+        [
+            (
+                "P3k3/8/8/8/8/8/8/4K2p",
+                [
+                    "a White pawn is stuck on a8",
+                    "a Black pawn is stuck on h1",
+                ],
+            ),
+            (
+                "4k3/8/8/8/8/8/8/K3K3",
+                [
+                    "White has 2 kings",
+                ],
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 - e1:11",
+                [
+                    "the King on e1 has a cooldown of 11, more than 10",
+                ],
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 - - R:7:6:h4:2:0:0:1",
+                [
+                    "the White Rook at (7, 6) does not reach h4 in 2 turns",
+                ],
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 - - R:7:6:h4:2:0:0:-1,Q:5:6:h4:2:0:1:-1",
+                [
+                    "the White Rook and Queen are both moving to h4",
+                ],
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 - - N:8.5:6:h4:1:0:0:-1",
+                [
+                    "the White Knight at (8.5, 6) is not on the board",
+                ],
+            ),
+            (
+                "overlapping kings",
+                [
+                    "the King and the King are both on e8",
+                    "Black has 2 kings",
+                ],
+            ),
+        ]"#
+    );
+}
+
+#[test]
+fn test_random_games_stay_valid() {
+    let mut rng = StdRng::seed_from_u64(19);
+    let constraints = PositionConstraints::midgame().with_moving_chance(0.3);
+    for _ in 0..20 {
+        let mut board = BoardState::generate_constrained_board_rng(&mut rng, &constraints).unwrap();
+        assert_eq!(
+            violations(&board),
+            Vec::<String>::new(),
+            "{}",
+            board.to_fen()
+        );
+        for _ in 0..30 {
+            let [white_move, black_move] = [Side::White, Side::Black].map(|side| {
+                let moves = board.get_all_possible_moves(side);
+                match moves.choose(&mut rng) {
+                    Some(board_move) if rng.gen_bool(0.5) => board_move.clone(),
+                    _ => BoardMove::None(side),
+                }
+            });
+            board.step(&white_move, &black_move);
+            assert_eq!(
+                violations(&board),
+                Vec::<String>::new(),
+                "{}",
+                board.to_fen()
+            );
+        }
+    }
+}
@@ -13,12 +13,70 @@ pub use position::*;
 mod board_util;
 pub use board_util::*;
 
+mod occupancy;
+pub use occupancy::*;
+
+mod config;
+pub use config::*;
+
+mod move_error;
+pub use move_error::*;
+
+mod invariant;
+pub use invariant::*;
+
+mod notation;
+
+mod generator;
+pub use generator::*;
+
+mod step_event;
+pub use step_event::*;
+
+mod zobrist;
+pub use zobrist::*;
+
+mod undo;
+pub use undo::*;
+
+mod variant;
+pub use variant::*;
+
+mod transform;
+
+mod perft;
+pub use perft::*;
+
+mod render;
+pub use render::*;
+
 #[cfg(test)]
 mod tests;
 
 #[cfg(test)]
 mod step_tests;
 
+#[cfg(test)]
+mod notation_tests;
+
+#[cfg(test)]
+mod generator_tests;
+
+#[cfg(test)]
+mod variant_tests;
+
+#[cfg(test)]
+mod transform_tests;
+
+#[cfg(test)]
+mod invariant_tests;
+
+#[cfg(test)]
+mod perft_tests;
+
+#[cfg(test)]
+mod render_tests;
+
 pub const BOARD_SIZE: usize = 8;
 pub const PIECE_COOLDOWN: u32 = 10;
 
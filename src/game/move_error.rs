@@ -0,0 +1,64 @@
+core!();
+
+use super::*;
+use thiserror::Error;
+
+// Why BoardState::validate_move rejected a move
+// the messages are meant to be shown to players as is
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
+pub enum MoveError {
+    #[error("the {kind:?} is already moving")]
+    PieceMoving { kind: PieceKind },
+    #[error("there is no {side:?} {kind:?} on {square}")]
+    NoSuchPiece {
+        side: Side,
+        kind: PieceKind,
+        square: Position,
+    },
+    #[error("the {kind:?} on {square} has to wait {turns_left} more turns before moving")]
+    OnCooldown {
+        kind: PieceKind,
+        square: Position,
+        turns_left: u32,
+    },
+    #[error("({}, {}) is not on the board", .square.x, .square.y)]
+    OffBoard { square: Position },
+    #[error("the {kind:?} is already on {square}")]
+    SameSquare { kind: PieceKind, square: Position },
+    #[error("{square} is occupied by a friendly piece")]
+    OccupiedByFriendly { square: Position },
+    #[error("{square} is already the target of a friendly moving piece")]
+    ReservedByFriendly { square: Position },
+    #[error("a {kind:?} cannot move from {from} to {to}")]
+    IllegalGeometry {
+        kind: PieceKind,
+        from: Position,
+        to: Position,
+    },
+    #[error("the path from {from} to {to} is blocked on {square}")]
+    PathBlocked {
+        from: Position,
+        to: Position,
+        square: Position,
+    },
+    #[error("promoting to {promotion:?} is not allowed for this move")]
+    InvalidPromotion { promotion: Option<PieceKind> },
+    #[error("{side:?} is no longer allowed to castle {}", castling_name(*.is_long_castle))]
+    NoCastlingRight { side: Side, is_long_castle: bool },
+    #[error("{side:?} cannot castle {} until the king and rook are ready", castling_name(*.is_long_castle))]
+    CastlingPiecesNotReady { side: Side, is_long_castle: bool },
+    #[error("{side:?} cannot castle {} because {square} is not empty", castling_name(*.is_long_castle))]
+    CastlingPathBlocked {
+        side: Side,
+        is_long_castle: bool,
+        square: Position,
+    },
+}
+
+fn castling_name(is_long_castle: bool) -> &'static str {
+    if is_long_castle {
+        "long"
+    } else {
+        "short"
+    }
+}
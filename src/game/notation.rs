@@ -0,0 +1,95 @@
+core!();
+
+use std::fmt::Display;
+
+use super::*;
+
+// Compact coordinate notation for moves: the side letter followed by
+// "b1c3" for a normal move (with "=Q" appended when a pawn picks its promotion),
+// "-" for no move, "O-O" for a short castle and "O-O-O" for a long castle
+impl Display for BoardMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", char::from(self.side()))?;
+        match self {
+            BoardMove::None(_) => write!(f, "-"),
+            BoardMove::LongCastle(_) => write!(f, "O-O-O"),
+            BoardMove::ShortCastle(_) => write!(f, "O-O"),
+            BoardMove::Normal {
+                piece,
+                target,
+                promotion,
+            } => {
+                let start = match piece.state {
+                    PieceState::Stationary { position, .. } => position,
+                    PieceState::Moving { x, y, .. } => Position::round_and_from(x, y),
+                };
+                write!(f, "{start}{target}")?;
+                if let Some(kind) = promotion {
+                    write!(f, "={}", char::from(*kind))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl BoardState {
+    // resolves the moving piece against this board, but does not check that the move is legal
+    // (use validate_move for that)
+    pub fn parse_move(&self, notation: &str) -> OrError<BoardMove> {
+        let mut chars = notation.chars();
+        let side = match chars.next() {
+            Some('W') => Side::White,
+            Some('B') => Side::Black,
+            _ => return Err(Error!("Unable to parse side in move {}", notation)),
+        };
+        let rest = chars.as_str();
+        match rest {
+            "-" => return Ok(BoardMove::None(side)),
+            "O-O" => return Ok(BoardMove::ShortCastle(side)),
+            "O-O-O" => return Ok(BoardMove::LongCastle(side)),
+            _ => {}
+        }
+        let (squares, promotion) = match rest.split_once('=') {
+            Some((squares, kind)) => {
+                let promotion = match kind.chars().collect::<Vec<_>>()[..] {
+                    [c] => PieceKind::from_char(c),
+                    _ => None,
+                }
+                .ok_or_else(|| Error!("Unable to parse promotion {} in {}", kind, notation))?;
+                (squares, Some(promotion))
+            }
+            None => (rest, None),
+        };
+        // the target square starts at the second file letter
+        let (start, target) = squares
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| c.is_ascii_alphabetic())
+            .map(|(i, _)| squares.split_at(i))
+            .ok_or_else(|| Error!("Unable to parse squares in move {}", notation))?;
+        let parse_square = |square: &str| {
+            Position::from_square_name(square)
+                .ok_or_else(|| Error!("Unable to parse square {} in {}", square, notation))
+        };
+        let start = parse_square(start)?;
+        let target = parse_square(target)?;
+        let is_start = |piece: &&Piece| {
+            piece.side == side
+                && matches!(piece.state, PieceState::Stationary { position, .. } if position == start)
+        };
+        let piece = self.pieces().iter().find(is_start).ok_or_else(|| {
+            Error!(
+                "No stationary {:?} piece on {} for move {}",
+                side,
+                start,
+                notation
+            )
+        })?;
+        Ok(BoardMove::Normal {
+            piece: *piece,
+            target,
+            promotion,
+        })
+    }
+}
@@ -0,0 +1,120 @@
+core!();
+
+use super::*;
+
+fn round_trip(board: &BoardState, side: Side) -> Vec<String> {
+    board
+        .get_all_possible_moves(side)
+        .iter()
+        .map(|board_move| {
+            let notation = board_move.to_string();
+            let parsed = board.parse_move(&notation).unwrap();
+            assert_eq!(parsed.to_string(), notation);
+            assert!(board.can_move(&parsed));
+            notation
+        })
+        .collect()
+}
+
+#[test]
+fn test_initial_moves_notation() {
+    let board = BoardState::new_initial_state();
+    expect!(
+        round_trip(&board, Side::White),
+        r#"
+        [
+            "Wa2a4",
+            "Wa2a3",
+            "Wb2b4",
+            "Wb2b3",
+            "Wc2c4",
+            "Wc2c3",
+            "Wd2d4",
+            "Wd2d3",
+            "We2e4",
+            "We2e3",
+            "Wf2f4",
+            "Wf2f3",
+            "Wg2g4",
+            "Wg2g3",
+            "Wh2h4",
+            "Wh2h3",
+            "Wb1a3",
+            "Wb1c3",
+            "Wg1f3",
+            "Wg1h3",
+            "W-",
+        ]"#
+    );
+}
+
+#[test]
+fn test_castling_and_promotion_notation() {
+    let board = BoardState::parse_fen("1k6/P7/8/8/8/8/8/R3K3 Q").unwrap();
+    expect!(
+        round_trip(&board, Side::White),
+        r#"
+        [
+            "WO-O-O",
+            "Wa7a8=Q",
+            "Wa7a8=R",
+            "Wa7a8=B",
+            "Wa7a8=N",
+            "Wa7b8=Q",
+            "Wa7b8=R",
+            "Wa7b8=B",
+            "Wa7b8=N",
+            "Wa1b1",
+            "Wa1c1",
+            "Wa1d1",
+            "Wa1a2",
+            "Wa1a3",
+            "Wa1a4",
+            "Wa1a5",
+            "Wa1a6",
+            "We1d2",
+            "We1d1",
+            "We1e2",
+            "We1f2",
+            "We1f1",
+            "W-",
+        ]"#
+    );
+    expect!(
+        round_trip(&board, Side::Black),
+        r#"
+        [
+            "Bb8a8",
+            "Bb8a7",
+            "Bb8b7",
+            "Bb8c8",
+            "Bb8c7",
+            "B-",
+        ]"#
+    );
+}
+
+#[test]
+fn test_parse_move_errors() {
+    let board = BoardState::new_initial_state();
+    let errors = [
+        "", "Xb1c3", "Wb1", "Wb1c9", "Wb1c3=X", "Wb1c3=QQ", "Wc3d5", "Bb1c3",
+    ]
+    .into_iter()
+    .map(|notation| board.parse_move(notation).unwrap_err().to_string())
+    .collect::<Vec<_>>();
+    expect!(
+        errors,
+        r#"
+        [
+            "Unable to parse side in move ",
+            "Unable to parse side in move Xb1c3",
+            "Unable to parse squares in move Wb1",
+            "Unable to parse square c9 in Wb1c9",
+            "Unable to parse promotion X in Wb1c3=X",
+            "Unable to parse promotion QQ in Wb1c3=QQ",
+            "No stationary White piece on c3 for move Wc3d5",
+            "No stationary Black piece on b1 for move Bb1c3",
+        ]"#
+    );
+}
@@ -0,0 +1,75 @@
+core!();
+
+use super::*;
+
+// one bit per square, indexed by y * BOARD_SIZE + x
+pub type Bitboard = u64;
+
+const _: () = assert!(BOARD_SIZE * BOARD_SIZE <= Bitboard::BITS as usize);
+
+pub fn to_bitboard(position: Position) -> Bitboard {
+    // off-board positions are treated as empty squares
+    if position.x < BOARD_SIZE as u32 && position.y < BOARD_SIZE as u32 {
+        1 << (position.y * BOARD_SIZE as u32 + position.x)
+    } else {
+        0
+    }
+}
+
+// Square-indexed view of BoardState::pieces so that lookups do not need to scan every piece
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) struct Occupancy {
+    stationary: EnumMap<Side, Bitboard>,
+    reserved: EnumMap<Side, Bitboard>, // targets of moving pieces
+}
+
+impl Occupancy {
+    pub fn new(pieces: &[Piece]) -> Self {
+        let mut occupancy = Self {
+            stationary: enum_map! { _ => 0 },
+            reserved: enum_map! { _ => 0 },
+        };
+        for piece in pieces {
+            occupancy.add(piece);
+        }
+        occupancy
+    }
+    pub fn add(&mut self, piece: &Piece) {
+        match piece.state {
+            PieceState::Stationary { position, .. } => {
+                self.stationary[piece.side] |= to_bitboard(position);
+            }
+            PieceState::Moving {
+                target: MoveTarget { target, .. },
+                ..
+            } => {
+                self.reserved[piece.side] |= to_bitboard(target);
+            }
+        }
+    }
+    pub fn remove(&mut self, piece: &Piece) {
+        match piece.state {
+            PieceState::Stationary { position, .. } => {
+                self.stationary[piece.side] &= !to_bitboard(position);
+            }
+            PieceState::Moving {
+                target: MoveTarget { target, .. },
+                ..
+            } => {
+                self.reserved[piece.side] &= !to_bitboard(target);
+            }
+        }
+    }
+    pub fn stationary(&self, side: Side) -> Bitboard {
+        self.stationary[side]
+    }
+    pub fn all_stationary(&self) -> Bitboard {
+        self.stationary[Side::White] | self.stationary[Side::Black]
+    }
+    pub fn reserved(&self, side: Side) -> Bitboard {
+        self.reserved[side]
+    }
+    pub fn all_reserved(&self) -> Bitboard {
+        self.reserved[Side::White] | self.reserved[Side::Black]
+    }
+}
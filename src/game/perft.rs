@@ -0,0 +1,68 @@
+core!();
+
+use std::collections::HashSet;
+
+use super::*;
+
+// What perft found at a given depth
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct PerftCounts {
+    pub move_pairs: u64, // sequences of (white move, black move) pairs, one pair per step
+    pub distinct_states: u64, // different boards those sequences end on
+}
+
+impl BoardState {
+    // counts every sequence of depth steps, trying all pairs of possible moves (including no move)
+    // at each step. games are not stopped when a king is taken, so that only move generation and
+    // step are measured. states are told apart by their zobrist hash
+    pub fn perft(&self, depth: u32) -> PerftCounts {
+        let mut board = self.clone();
+        let mut states = HashSet::new();
+        let move_pairs = board.perft_into(depth, &mut states);
+        PerftCounts {
+            move_pairs,
+            distinct_states: states.len() as u64,
+        }
+    }
+
+    // perft(depth) broken down by the pair of moves of the first step, in move generation order
+    pub fn perft_divide(&self, depth: u32) -> Vec<(BoardMove, BoardMove, PerftCounts)> {
+        debug_assert!(depth >= 1);
+        let mut board = self.clone();
+        self.get_all_move_pairs()
+            .into_iter()
+            .map(|(white_move, black_move)| {
+                let undo = board.step_reversibly(&white_move, &black_move);
+                let counts = board.perft(depth - 1);
+                board.undo(undo);
+                (white_move, black_move, counts)
+            })
+            .collect()
+    }
+
+    fn perft_into(&mut self, depth: u32, states: &mut HashSet<ZobristHash>) -> u64 {
+        if depth == 0 {
+            states.insert(self.zobrist_hash());
+            return 1;
+        }
+        let mut move_pairs = 0;
+        for (white_move, black_move) in self.get_all_move_pairs() {
+            let undo = self.step_reversibly(&white_move, &black_move);
+            move_pairs += self.perft_into(depth - 1, states);
+            self.undo(undo);
+        }
+        move_pairs
+    }
+
+    fn get_all_move_pairs(&self) -> Vec<(BoardMove, BoardMove)> {
+        let black_moves = self.get_all_possible_moves(Side::Black);
+        self.get_all_possible_moves(Side::White)
+            .into_iter()
+            .flat_map(|white_move| {
+                black_moves
+                    .iter()
+                    .map(move |black_move| (white_move.clone(), black_move.clone()))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,132 @@
+core!();
+
+use super::*;
+
+fn perft_counts(fen: &str, max_depth: u32) -> Vec<(u32, u64, u64)> {
+    let board = BoardState::parse_fen(fen).unwrap();
+    (0..=max_depth)
+        .map(|depth| {
+            let counts = board.perft(depth);
+            (depth, counts.move_pairs, counts.distinct_states)
+        })
+        .collect()
+}
+
+fn perft_divide_summary(fen: &str, depth: u32) -> Vec<String> {
+    BoardState::parse_fen(fen)
+        .unwrap()
+        .perft_divide(depth)
+        .into_iter()
+        .map(|(white_move, black_move, counts)| {
+            format!(
+                "{white_move} {black_move}: {} {}",
+                counts.move_pairs, counts.distinct_states
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_perft_initial_state() {
+    expect!(
+        perft_counts("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", 2),
+        r#"
+        [
+            (
+                0,
+                1,
+                1,
+            ),
+            (
+                1,
+                441,
+                441,
+            ),
+            (
+                2,
+                209764,
+                209764,
+            ),
+        ]"#
+    );
+}
+
+#[test]
+fn test_perft_castling() {
+    expect!(
+        perft_counts("r3k2r/8/8/8/8/8/8/R3K2R KQkq", 2),
+        r#"
+        [
+            (
+                0,
+                1,
+                1,
+            ),
+            (
+                1,
+                729,
+                729,
+            ),
+            (
+                2,
+                231236,
+                231236,
+            ),
+        ]"#
+    );
+}
+
+#[test]
+fn test_perft_promotion_and_captures() {
+    expect!(
+        perft_counts("4k3/1P6/8/3p4/4P3/8/8/4K3", 3),
+        r#"
+        [
+            (
+                0,
+                1,
+                1,
+            ),
+            (
+                1,
+                96,
+                94,
+            ),
+            (
+                2,
+                3105,
+                2864,
+            ),
+            (
+                3,
+                29482,
+                25409,
+            ),
+        ]"#
+    );
+}
+
+#[test]
+fn test_perft_divide() {
+    let fen = "k7/8/8/8/8/8/8/4K2R K";
+    // only the entries where white castles, against every black move
+    let castles = perft_divide_summary(fen, 2)
+        .into_iter()
+        .filter(|line| line.starts_with("WO-O"))
+        .collect::<Vec<_>>();
+    expect!(
+        castles,
+        r#"
+        [
+            "WO-O Ba8a7: 1 1",
+            "WO-O Ba8b8: 1 1",
+            "WO-O Ba8b7: 1 1",
+            "WO-O B-: 4 4",
+        ]"#
+    );
+    let board = BoardState::parse_fen(fen).unwrap();
+    let divide = board.perft_divide(2);
+    let move_pairs: u64 = divide.iter().map(|(_, _, counts)| counts.move_pairs).sum();
+    assert_eq!(move_pairs, board.perft(2).move_pairs);
+    assert_eq!(divide.len() as u64, board.perft(1).move_pairs);
+}
@@ -26,6 +26,17 @@ impl PieceKind {
     }
 }
 
+lazy_static! {
+    pub static ref MATERIAL_VALUE: EnumMap<PieceKind, u32> = enum_map! {
+        PieceKind::Pawn => 1,
+        PieceKind::Knight => 3,
+        PieceKind::Bishop => 3,
+        PieceKind::Rook => 5,
+        PieceKind::Queen => 9,
+        PieceKind::King => 100,
+    };
+}
+
 impl From<PieceKind> for char {
     fn from(kind: PieceKind) -> Self {
         match kind {
@@ -45,6 +56,15 @@ pub enum Side {
     Black,
 }
 
+impl Side {
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+        }
+    }
+}
+
 impl From<Side> for char {
     fn from(side: Side) -> Self {
         match side {
@@ -84,11 +104,19 @@ pub struct MoveTarget {
     // piece that moves first gets precedence (and eats opposing pieces in its path - the path is blocked off for its own pieces for the duration of its move)
     pub priority: u32, // priority gets incremented at every step
     pub velocity: (f32, f32),
+    pub promotion: Option<PieceKind>, // what a pawn turns into when it arrives on the last rank
 }
 
 impl MoveTarget {
     pub const MIN_PRIORITY: u32 = 0;
-    pub fn new(current: Position, target: Position, turns_left: u32, priority: u32) -> Self {
+    pub fn new(
+        config: &GameConfig,
+        kind: PieceKind,
+        current: Position,
+        target: Position,
+        priority: u32,
+    ) -> Self {
+        let turns_left = config.travel_time(kind, current, target);
         let vx = ((target.x as f32) - (current.x as f32)) / (turns_left as f32);
         let vy = ((target.y as f32) - (current.y as f32)) / (turns_left as f32);
         Self {
@@ -96,6 +124,7 @@ impl MoveTarget {
             turns_left,
             priority,
             velocity: (vx, vy),
+            promotion: None,
         }
     }
 }
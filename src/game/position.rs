@@ -1,4 +1,9 @@
-use std::ops::{Add, AddAssign, Sub};
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Sub},
+};
+
+use super::BOARD_SIZE;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Position {
@@ -13,6 +18,29 @@ impl Position {
             y: y.round() as u32,
         }
     }
+    // parses algebraic notation (ex: "a1" is the bottom left corner from white's perspective)
+    pub fn from_square_name(name: &str) -> Option<Self> {
+        let mut chars = name.chars();
+        let file = chars.next()?;
+        let rank = chars.as_str().parse::<u32>().ok()?;
+        let x = (file as u32).checked_sub('a' as u32)?;
+        if x < BOARD_SIZE as u32 && (1..=BOARD_SIZE as u32).contains(&rank) {
+            Some(Self {
+                x,
+                y: BOARD_SIZE as u32 - rank,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let file = char::from_u32('a' as u32 + self.x).unwrap_or('?');
+        let rank = BOARD_SIZE as i64 - self.y as i64;
+        write!(f, "{file}{rank}")
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -0,0 +1,98 @@
+core!();
+
+use super::*;
+use itertools::Itertools;
+
+// How BoardState::render draws pieces
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RenderStyle {
+    Ascii,   // FEN letters, with '.' for empty squares
+    Unicode, // chess glyphs, with '·' for empty squares
+}
+
+impl RenderStyle {
+    pub fn piece_char(self, side: Side, kind: PieceKind) -> char {
+        match self {
+            RenderStyle::Ascii => piece_to_fen_char(side, kind),
+            RenderStyle::Unicode => match (side, kind) {
+                (Side::White, PieceKind::Pawn) => '♙',
+                (Side::White, PieceKind::Knight) => '♘',
+                (Side::White, PieceKind::Bishop) => '♗',
+                (Side::White, PieceKind::Rook) => '♖',
+                (Side::White, PieceKind::Queen) => '♕',
+                (Side::White, PieceKind::King) => '♔',
+                (Side::Black, PieceKind::Pawn) => '♟',
+                (Side::Black, PieceKind::Knight) => '♞',
+                (Side::Black, PieceKind::Bishop) => '♝',
+                (Side::Black, PieceKind::Rook) => '♜',
+                (Side::Black, PieceKind::Queen) => '♛',
+                (Side::Black, PieceKind::King) => '♚',
+            },
+        }
+    }
+    fn empty(self) -> char {
+        match self {
+            RenderStyle::Ascii => '.',
+            RenderStyle::Unicode => '·',
+        }
+    }
+}
+
+impl BoardState {
+    // Every piece, including the ones in flight. each square is drawn as two characters:
+    // the piece and a mark, which is
+    //     '>' for a moving piece, drawn on the square it is closest to
+    //     the cooldown for a stationary piece (or 'X' for 10 and more)
+    //     '*' for an empty square that a moving piece is headed to
+    // followed by one line per moving piece, with where it is, its target and the turns left
+    // (ex: "R h2>h4 2")
+    pub fn render(&self, style: RenderStyle) -> String {
+        let mut squares = [[(style.empty(), ' '); BOARD_SIZE]; BOARD_SIZE];
+        let mut moving_lines = Vec::new();
+        for piece in self.pieces() {
+            if let PieceState::Moving { target, .. } = piece.state {
+                let square = &mut squares[target.target.y as usize][target.target.x as usize];
+                if square.0 == style.empty() {
+                    *square = (style.empty(), '*');
+                }
+            }
+        }
+        for piece in self.pieces() {
+            let glyph = style.piece_char(piece.side, piece.kind);
+            let (position, mark) = match piece.state {
+                PieceState::Stationary { position, cooldown } => {
+                    let mark = match cooldown {
+                        0 => ' ',
+                        1..=9 => char::from_digit(cooldown, 10).unwrap(),
+                        _ => 'X',
+                    };
+                    (position, mark)
+                }
+                PieceState::Moving { x, y, target } => {
+                    let position = Position::round_and_from(x, y);
+                    moving_lines.push(format!(
+                        "{glyph} {position}>{} {}",
+                        target.target, target.turns_left
+                    ));
+                    (position, '>')
+                }
+            };
+            let square = &mut squares[position.y as usize][position.x as usize];
+            // a moving piece stays visible when it passes over a stationary one
+            if !(piece.state.is_stationary() && square.1 == '>') {
+                *square = (glyph, mark);
+            }
+        }
+        squares
+            .iter()
+            .map(|row| {
+                let line: String = row
+                    .iter()
+                    .flat_map(|&(glyph, mark)| [glyph, mark])
+                    .collect();
+                line.trim_end().to_owned()
+            })
+            .chain(moving_lines)
+            .join("\n")
+    }
+}
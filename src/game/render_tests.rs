@@ -0,0 +1,65 @@
+core!();
+
+use super::*;
+
+fn mid_move_board() -> BoardState {
+    // the rook is on its way from h1 to h4, and the queen has just arrived on d1
+    BoardState::parse_fen("r3k2r/8/8/8/8/8/8/R2QK3 Qk d1:3 R:7:6:h4:2:0:0:-1").unwrap()
+}
+
+#[test]
+fn test_render_ascii() {
+    expect!(
+        mid_move_board()
+            .render(RenderStyle::Ascii)
+            .lines()
+            .collect::<Vec<_>>(),
+        r#"
+        [
+            "r . . . k . . r",
+            ". . . . . . . .",
+            ". . . . . . . .",
+            ". . . . . . . .",
+            ". . . . . . . .*",
+            ". . . . . . . .",
+            ". . . . . . . R>",
+            "R . . Q3K . . .",
+            "R h2>h4 2",
+        ]"#
+    );
+}
+
+#[test]
+fn test_render_unicode() {
+    expect!(
+        mid_move_board()
+            .render(RenderStyle::Unicode)
+            .lines()
+            .collect::<Vec<_>>(),
+        r#"
+        [
+            "♜ · · · ♚ · · ♜",
+            "· · · · · · · ·",
+            "· · · · · · · ·",
+            "· · · · · · · ·",
+            "· · · · · · · ·*",
+            "· · · · · · · ·",
+            "· · · · · · · ♖>",
+            "♖ · · ♕3♔ · · ·",
+            "♖ h2>h4 2",
+        ]"#
+    );
+}
+
+#[test]
+fn test_render_stationary_board() {
+    // without pieces in flight or cooldowns, only the spacing differs from to_stationary_map_combo
+    let board = BoardState::new_initial_state();
+    let spaced_map = board
+        .to_stationary_map_combo()
+        .lines()
+        .map(|line| line.chars().map(String::from).collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert_eq!(board.render(RenderStyle::Ascii), spaced_map);
+}
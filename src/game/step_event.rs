@@ -0,0 +1,44 @@
+core!();
+
+use super::*;
+
+// Something that happened to a piece during BoardState::step_with_events
+// tick is the number of ticks into the call at which it happened (0 for a single step)
+#[derive(Debug, Copy, Clone)]
+pub enum StepEvent {
+    // victim and capturer are as they were at the start of the tick
+    Captured {
+        victim: Piece,
+        capturer: Piece,
+        tick: u32,
+    },
+    // piece is already stationary (and promoted, if it is a pawn reaching the last rank)
+    Arrived {
+        piece: Piece,
+        square: Position,
+        tick: u32,
+    },
+    // always follows the Arrived event of the same pawn
+    Promoted {
+        piece: Piece,
+        square: Position,
+        tick: u32,
+    },
+    // the piece can move again
+    CooldownExpired {
+        piece: Piece,
+        square: Position,
+        tick: u32,
+    },
+}
+
+impl StepEvent {
+    pub fn tick(&self) -> u32 {
+        match self {
+            StepEvent::Captured { tick, .. }
+            | StepEvent::Arrived { tick, .. }
+            | StepEvent::Promoted { tick, .. }
+            | StepEvent::CooldownExpired { tick, .. } => *tick,
+        }
+    }
+}
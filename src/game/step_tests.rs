@@ -16,12 +16,19 @@ where
 }
 
 fn snapshots_until_stationary(board: &mut BoardState) -> Vec<String> {
+    snapshots_until_stationary_with(board, BoardState::to_stationary_map_combo)
+}
+
+fn snapshots_until_stationary_with<F>(board: &mut BoardState, f: F) -> Vec<String>
+where
+    F: Fn(&BoardState) -> String,
+{
     let mut all_steps = Vec::new();
     while !board.is_all_pieces_stationary() {
-        all_steps.push(board.to_stationary_map_combo());
+        all_steps.push(f(board));
         board.step_without_moves();
     }
-    all_steps.push(board.to_stationary_map_combo());
+    all_steps.push(f(board));
     all_steps
 }
 
@@ -85,6 +92,7 @@ fn test_step_rand_move() {
                 x: 1,
                 y: 1,
             },
+            promotion: None,
         }"#
     );
     expect!(
@@ -109,6 +117,7 @@ fn test_step_capture_stationary() {
     let board_move = BoardMove::Normal {
         piece: *queen,
         target: (5u32, 3u32).into(),
+        promotion: None,
     };
     board.apply_move(&board_move);
     let snapshots = snapshots_until_stationary(&mut board);
@@ -131,6 +140,7 @@ fn test_step_capture_stationary() {
                 x: 5,
                 y: 3,
             },
+            promotion: None,
         }"#
     );
     expect!(
@@ -243,6 +253,7 @@ fn test_has_move_after_cooldown() {
                 x: 4,
                 y: 2,
             },
+            promotion: None,
         }"#
     );
 }
@@ -270,6 +281,9 @@ fn test_pawn_promotion() {
                 x: 0,
                 y: 0,
             },
+            promotion: Some(
+                Queen,
+            ),
         }"#
     );
     board.step(&white_move, &BoardMove::None(Side::Black));
@@ -279,6 +293,70 @@ fn test_pawn_promotion() {
     );
 }
 
+#[test]
+fn test_pawn_under_promotion() {
+    let mut board = BoardState::parse_fen("1r6/P7/8/8/8/8/8/8").unwrap();
+    let promotions = board
+        .get_all_possible_moves(Side::White)
+        .into_iter()
+        .filter_map(|board_move| match board_move {
+            BoardMove::Normal {
+                target, promotion, ..
+            } => Some(format!("{target} {promotion:?}")),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    expect!(
+        promotions,
+        r#"
+        [
+            "a8 Some(Queen)",
+            "a8 Some(Rook)",
+            "a8 Some(Bishop)",
+            "a8 Some(Knight)",
+            "b8 Some(Queen)",
+            "b8 Some(Rook)",
+            "b8 Some(Bishop)",
+            "b8 Some(Knight)",
+        ]"#
+    );
+    let white_move = find_move(&board, Side::White, |board_move| {
+        matches!(
+            board_move,
+            BoardMove::Normal {
+                promotion: Some(PieceKind::Knight),
+                ..
+            }
+        )
+    })
+    .unwrap();
+    board.step(&white_move, &BoardMove::None(Side::Black));
+    expect!(
+        board.to_stationary_map_combo(),
+        r#""Nr......\n........\n........\n........\n........\n........\n........\n........""#
+    );
+}
+
+#[test]
+fn test_promotion_must_match_rule() {
+    let board = BoardState::parse_fen("8/P7/8/8/8/8/8/8").unwrap();
+    let pawn = board.pieces()[0];
+    let promotion_move = |target: Position, promotion: Option<PieceKind>| BoardMove::Normal {
+        piece: pawn,
+        target,
+        promotion,
+    };
+    // the player has to pick a kind when reaching the last rank
+    assert!(!board.can_move(&promotion_move((0u32, 0u32).into(), None)));
+    assert!(!board.can_move(&promotion_move((0u32, 0u32).into(), Some(PieceKind::King))));
+    assert!(!board.can_move(&promotion_move((0u32, 0u32).into(), Some(PieceKind::Pawn))));
+    assert!(board.can_move(&promotion_move((0u32, 0u32).into(), Some(PieceKind::Rook))));
+    // but cannot pick one when the rule does not allow a choice
+    let board = board.with_config(GameConfig::standard().with_promotion(PromotionRule::Never));
+    assert!(board.can_move(&promotion_move((0u32, 0u32).into(), None)));
+    assert!(!board.can_move(&promotion_move((0u32, 0u32).into(), Some(PieceKind::Rook))));
+}
+
 #[test]
 fn test_step_until_stationary_with_no_cooldown() {
     let mut board = BoardState::parse_fen("8/8/8/P7/8/8/8/8").unwrap();
@@ -308,3 +386,551 @@ fn test_bishop_captures() {
         r#"".r......\nr.r.....\n.r.r....\n..r.r...\n...r.r..\n....r.r.\n.....r.r\n......rB""#
     );
 }
+
+fn castling_board() -> BoardState {
+    // only kings, rooks and pawns remain on their starting squares
+    let mut board = BoardState::new_initial_state();
+    board.pieces_mut().retain(|piece| {
+        matches!(
+            piece.kind,
+            PieceKind::King | PieceKind::Rook | PieceKind::Pawn
+        )
+    });
+    board
+}
+
+fn has_castling_moves(board: &BoardState, side: Side) -> (bool, bool) {
+    let moves = board.get_all_possible_moves(side);
+    (
+        moves.iter().any(|m| matches!(m, BoardMove::LongCastle(_))),
+        moves.iter().any(|m| matches!(m, BoardMove::ShortCastle(_))),
+    )
+}
+
+#[test]
+fn test_no_castling_in_initial_state() {
+    let board = BoardState::new_initial_state();
+    expect!(
+        has_castling_moves(&board, Side::White),
+        r#"
+        (
+            false,
+            false,
+        )"#
+    );
+}
+
+#[test]
+fn test_short_castle() {
+    let mut board = castling_board();
+    expect!(
+        has_castling_moves(&board, Side::White),
+        r#"
+        (
+            true,
+            true,
+        )"#
+    );
+    board.apply_move(&BoardMove::ShortCastle(Side::White));
+    let snapshots = snapshots_until_stationary(&mut board);
+    expect!(
+        snapshots,
+        r#"
+        [
+            "r...k..r\npppppppp\n........\n........\n........\n........\nPPPPPPPP\nR.......",
+            "r...k..r\npppppppp\n........\n........\n........\n........\nPPPPPPPP\nR.......",
+            "r...k..r\npppppppp\n........\n........\n........\n........\nPPPPPPPP\nR....RK.",
+        ]"#
+    );
+    expect!(
+        has_castling_moves(&board, Side::White),
+        r#"
+        (
+            false,
+            false,
+        )"#
+    );
+}
+
+#[test]
+fn test_short_castle_rendered() {
+    // unlike the stationary map, the king and rook stay visible while they are in flight
+    let mut board = castling_board();
+    board.apply_move(&BoardMove::ShortCastle(Side::White));
+    let snapshots =
+        snapshots_until_stationary_with(&mut board, |board| board.render(RenderStyle::Ascii));
+    expect!(
+        snapshots,
+        r#"
+        [
+            "r . . . k . . r\np p p p p p p p\n. . . . . . . .\n. . . . . . . .\n. . . . . . . .\n. . . . . . . .\nP P P P P P P P\nR . . . K>.*.*R>\nR h1>f1 2\nK e1>g1 2",
+            "r . . . k . . r\np p p p p p p p\n. . . . . . . .\n. . . . . . . .\n. . . . . . . .\n. . . . . . . .\nP P P P P P P P\nR . . . . K>R>.\nR g1>f1 1\nK f1>g1 1",
+            "r . . . k . . r\np p p p p p p p\n. . . . . . . .\n. . . . . . . .\n. . . . . . . .\n. . . . . . . .\nP P P P P P P P\nR . . . . RXKX.",
+        ]"#
+    );
+}
+
+#[test]
+fn test_long_castle() {
+    let mut board = castling_board();
+    board.step(
+        &BoardMove::None(Side::White),
+        &BoardMove::LongCastle(Side::Black),
+    );
+    let snapshots = snapshots_until_stationary(&mut board);
+    expect!(
+        snapshots,
+        r#"
+        [
+            ".......r\npppppppp\n........\n........\n........\n........\nPPPPPPPP\nR...K..R",
+            "..k....r\npppppppp\n........\n........\n........\n........\nPPPPPPPP\nR...K..R",
+            "..kr...r\npppppppp\n........\n........\n........\n........\nPPPPPPPP\nR...K..R",
+        ]"#
+    );
+}
+
+#[test]
+fn test_castle_blocked() {
+    let mut board = castling_board();
+    let knight = Piece {
+        side: Side::Black,
+        kind: PieceKind::Knight,
+        state: PieceState::Stationary {
+            position: (1u32, 7u32).into(),
+            cooldown: 0,
+        },
+    };
+    board.pieces_mut().push(knight);
+    expect!(
+        has_castling_moves(&board, Side::White),
+        r#"
+        (
+            false,
+            true,
+        )"#
+    );
+}
+
+#[test]
+fn test_no_castle_while_cooldown() {
+    let mut board = castling_board();
+    board.pieces_mut().iter_mut().for_each(|piece| {
+        if piece.kind == PieceKind::Rook && let PieceState::Stationary { cooldown, .. } = &mut piece.state {
+            *cooldown = 5;
+        }
+    });
+    expect!(
+        has_castling_moves(&board, Side::White),
+        r#"
+        (
+            false,
+            false,
+        )"#
+    );
+    board.step_n(5);
+    expect!(
+        has_castling_moves(&board, Side::White),
+        r#"
+        (
+            true,
+            true,
+        )"#
+    );
+}
+
+#[test]
+fn test_castling_rights_revoked_after_king_move() {
+    fn find_king_move(board: &BoardState, target: Position) -> Option<BoardMove> {
+        find_move(
+            board,
+            Side::White,
+            |p| matches!(p, BoardMove::Normal { piece, target: move_target, .. } if piece.kind == PieceKind::King && move_target == &target),
+        )
+    }
+    let mut board = castling_board();
+    let king_move = find_king_move(&board, (5u32, 7u32).into()).unwrap();
+    board.step(&king_move, &BoardMove::None(Side::Black));
+    board.step_until_stationary_with_no_cooldown();
+    let king_move = find_king_move(&board, (4u32, 7u32).into()).unwrap();
+    board.step(&king_move, &BoardMove::None(Side::Black));
+    board.step_until_stationary_with_no_cooldown();
+    expect!(
+        board.to_stationary_map_combo(),
+        r#""r...k..r\npppppppp\n........\n........\n........\n........\nPPPPPPPP\nR...K..R""#
+    );
+    expect!(
+        has_castling_moves(&board, Side::White),
+        r#"
+        (
+            false,
+            false,
+        )"#
+    );
+    expect!(
+        has_castling_moves(&board, Side::Black),
+        r#"
+        (
+            true,
+            true,
+        )"#
+    );
+}
+
+#[test]
+fn test_castling_rights_revoked_after_rook_captured() {
+    let mut board = castling_board();
+    board.pieces_mut().retain(|piece| {
+        !matches!(piece.state, PieceState::Stationary { position, .. } if position.x == 7 && piece.kind == PieceKind::Pawn)
+    });
+    let black_move = find_move_by_target(&board, Side::Black, (7u32, 7u32).into()).unwrap();
+    board.step(&BoardMove::None(Side::White), &black_move);
+    board.step_until_stationary_with_no_cooldown();
+    expect!(
+        board.to_stationary_map_combo(),
+        r#""r...k...\nppppppp.\n........\n........\n........\n........\nPPPPPPP.\nR...K..r""#
+    );
+    expect!(
+        has_castling_moves(&board, Side::White),
+        r#"
+        (
+            true,
+            false,
+        )"#
+    );
+    expect!(
+        has_castling_moves(&board, Side::Black),
+        r#"
+        (
+            true,
+            false,
+        )"#
+    );
+}
+
+#[test]
+fn test_lightning_cooldown() {
+    let mut board = BoardState::parse_fen("8/8/8/8/4P3/8/8/8")
+        .unwrap()
+        .with_config(GameConfig::lightning());
+    let board_move = find_move_by_kind(&board, Side::White, PieceKind::Pawn).unwrap();
+    board.apply_move(&board_move);
+    let snapshots = snapshots_n(&mut board, 4, |x| x.to_stationary_map_cooldowns());
+    expect!(
+        snapshots,
+        r#"
+        [
+            "........\n........\n........\n........\n........\n........\n........\n........",
+            "........\n........\n........\n....2...\n........\n........\n........\n........",
+            "........\n........\n........\n....1...\n........\n........\n........\n........",
+            "........\n........\n........\n....0...\n........\n........\n........\n........",
+            "........\n........\n........\n....0...\n........\n........\n........\n........",
+        ]"#
+    );
+}
+
+#[test]
+fn test_slow_knight() {
+    let mut board = BoardState::parse_fen("8/8/8/8/8/8/8/1N6")
+        .unwrap()
+        .with_config(GameConfig::standard().with_ticks_per_square(PieceKind::Knight, 2));
+    let board_move = find_move_by_target(&board, Side::White, (2u32, 5u32).into()).unwrap();
+    board.apply_move(&board_move);
+    let snapshots = snapshots_n(&mut board, 4, |x| x.to_stationary_map_combo());
+    expect!(
+        snapshots,
+        r#"
+        [
+            "........\n........\n........\n........\n........\n........\n........\n........",
+            "........\n........\n........\n........\n........\n........\n........\n........",
+            "........\n........\n........\n........\n........\n........\n........\n........",
+            "........\n........\n........\n........\n........\n........\n........\n........",
+            "........\n........\n........\n........\n........\n..N.....\n........\n........",
+        ]"#
+    );
+}
+
+#[test]
+fn test_promotion_rules() {
+    let promote = |promotion: PromotionRule| {
+        let mut board = BoardState::parse_fen("8/P7/8/8/8/8/8/8")
+            .unwrap()
+            .with_config(GameConfig::standard().with_promotion(promotion));
+        let white_move = find_move_by_kind(&board, Side::White, PieceKind::Pawn).unwrap();
+        board.step(&white_move, &BoardMove::None(Side::Black));
+        board.to_stationary_map_combo()
+    };
+    expect!(
+        promote(PromotionRule::Always(PieceKind::Knight)),
+        r#""N.......\n........\n........\n........\n........\n........\n........\n........""#
+    );
+    expect!(
+        promote(PromotionRule::Never),
+        r#""P.......\n........\n........\n........\n........\n........\n........\n........""#
+    );
+}
+
+#[test]
+fn test_step_n_equivalent_to_single_steps() {
+    let mut board = BoardState::new_initial_state();
+    for i in 0..60 {
+        // keep starting moves for both sides so that several pieces are in flight at once
+        let white_moves = board.get_all_possible_moves(Side::White);
+        let black_moves = board.get_all_possible_moves(Side::Black);
+        board.step(
+            &white_moves[(i * 7) % white_moves.len()],
+            &black_moves[(i * 11) % black_moves.len()],
+        );
+        for n in [1, 2, 3, 5, 8] {
+            let mut fast = board.clone();
+            fast.step_n(n);
+            let mut slow = board.clone();
+            for _ in 0..n {
+                slow.step_without_moves();
+            }
+            assert_eq!(fast.to_fen(), slow.to_fen());
+        }
+        let mut fast = board.clone();
+        fast.step_until_stationary_with_no_cooldown();
+        let mut slow = board.clone();
+        while !slow.is_all_pieces_stationary_with_no_cooldown() {
+            slow.step_without_moves();
+        }
+        assert_eq!(fast.to_fen(), slow.to_fen());
+    }
+}
+
+fn to_event_summary(event: &StepEvent) -> String {
+    let to_name = |piece: &Piece| format!("{}{}", char::from(piece.side), char::from(piece.kind));
+    match event {
+        StepEvent::Captured {
+            victim, capturer, ..
+        } => format!("Captured {} by {}", to_name(victim), to_name(capturer)),
+        StepEvent::Arrived { piece, square, .. } => {
+            format!("Arrived {} {}", to_name(piece), square)
+        }
+        StepEvent::Promoted { piece, square, .. } => {
+            format!("Promoted {} {}", to_name(piece), square)
+        }
+        StepEvent::CooldownExpired { piece, square, .. } => {
+            format!("CooldownExpired {} {}", to_name(piece), square)
+        }
+    }
+}
+
+#[test]
+fn test_step_events_capture() {
+    let mut board = BoardState::parse_fen("3N4/b3P3/5p1B/2Q2bPP/PnK5/r5N1/7k/3r4")
+        .unwrap()
+        .with_config(GameConfig::lightning());
+    let queen_move = find_move_by_target(&board, Side::White, (5u32, 3u32).into()).unwrap();
+    let mut white_move = queen_move;
+    let mut all_events = Vec::new();
+    while !board.is_all_pieces_stationary_with_no_cooldown() || all_events.is_empty() {
+        let events = board.step_with_events(&white_move, &BoardMove::None(Side::Black));
+        assert!(events.iter().all(|event| event.tick() == 0));
+        all_events.push(events.iter().map(to_event_summary).collect::<Vec<_>>());
+        white_move = BoardMove::None(Side::White);
+    }
+    expect!(
+        all_events,
+        r#"
+        [
+            [],
+            [],
+            [
+                "Captured BB by WQ",
+                "Arrived WQ f5",
+            ],
+            [],
+            [
+                "CooldownExpired WQ f5",
+            ],
+        ]"#
+    );
+}
+
+#[test]
+fn test_step_events_promotion() {
+    let mut board = BoardState::parse_fen("8/P7/8/8/8/8/8/8").unwrap();
+    let white_move = find_move(&board, Side::White, |board_move| {
+        matches!(
+            board_move,
+            BoardMove::Normal {
+                promotion: Some(PieceKind::Knight),
+                ..
+            }
+        )
+    })
+    .unwrap();
+    let events = board.step_with_events(&white_move, &BoardMove::None(Side::Black));
+    expect!(
+        events.iter().map(to_event_summary).collect::<Vec<_>>(),
+        r#"
+        [
+            "Arrived WN a8",
+            "Promoted WN a8",
+        ]"#
+    );
+}
+
+fn piece_at(board: &BoardState, position: Position) -> Piece {
+    *board
+        .pieces()
+        .iter()
+        .find(|piece| matches!(piece.state, PieceState::Stationary { position: p, .. } if p == position))
+        .unwrap()
+}
+
+#[test]
+fn test_validate_move_errors() {
+    let mut board = BoardState::parse_fen("8/8/8/8/8/1p6/PP6/R1B1K3").unwrap();
+    let normal_move = |board: &BoardState, from: (u32, u32), to: (u32, u32)| BoardMove::Normal {
+        piece: piece_at(board, from.into()),
+        target: to.into(),
+        promotion: None,
+    };
+    let describe = |board: &BoardState, board_move: &BoardMove| {
+        board
+            .validate_move(board_move)
+            .map_err(|error| error.to_string())
+    };
+    let mut results = [
+        ((0, 6), (1, 5)),
+        ((1, 6), (1, 5)),
+        ((0, 6), (0, 3)),
+        ((0, 7), (0, 5)),
+        ((0, 7), (0, 6)),
+        ((2, 7), (2, 6)),
+        ((4, 7), (4, 7)),
+        ((4, 7), (8, 7)),
+    ]
+    .into_iter()
+    .map(|(from, to)| describe(&board, &normal_move(&board, from, to)))
+    .collect::<Vec<_>>();
+    // moves built from a piece that is not on the board
+    let mut queen = piece_at(&board, (0u32, 7u32).into());
+    queen.kind = PieceKind::Queen;
+    let queen_move = BoardMove::Normal {
+        piece: queen,
+        target: (0u32, 6u32).into(),
+        promotion: None,
+    };
+    results.push(describe(&board, &queen_move));
+    // the bishop cannot go where the king is already heading
+    let bishop_move = normal_move(&board, (2, 7), (3, 6));
+    board.apply_move(&normal_move(&board, (4, 7), (3, 6)));
+    results.push(describe(&board, &bishop_move));
+    board.step_n(1);
+    results.push(describe(&board, &normal_move(&board, (3, 6), (3, 5))));
+    expect!(
+        results,
+        r#"
+        [
+            Ok(
+                (),
+            ),
+            Err(
+                "the path from b2 to b3 is blocked on b3",
+            ),
+            Err(
+                "a Pawn cannot move from a2 to a5",
+            ),
+            Err(
+                "the path from a1 to a3 is blocked on a2",
+            ),
+            Err(
+                "a2 is occupied by a friendly piece",
+            ),
+            Err(
+                "a Bishop cannot move from c1 to c2",
+            ),
+            Err(
+                "the King is already on e1",
+            ),
+            Err(
+                "(8, 7) is not on the board",
+            ),
+            Err(
+                "there is no White Queen on a1",
+            ),
+            Err(
+                "d2 is already the target of a friendly moving piece",
+            ),
+            Err(
+                "the King on d2 has to wait 10 more turns before moving",
+            ),
+        ]"#
+    );
+    // can_move agrees with validate_move
+    assert!(board.can_move(&normal_move(&board, (0, 6), (1, 5))));
+    assert!(!board.can_move(&normal_move(&board, (1, 6), (1, 5))));
+}
+
+#[test]
+fn test_validate_castle_errors() {
+    let mut board = castling_board();
+    let knight = Piece {
+        side: Side::White,
+        kind: PieceKind::Knight,
+        state: PieceState::Stationary {
+            position: (1u32, 7u32).into(),
+            cooldown: 0,
+        },
+    };
+    board.pieces_mut().push(knight);
+    board.pieces_mut().iter_mut().for_each(|piece| {
+        if piece.kind == PieceKind::Rook && let PieceState::Stationary { position, cooldown } = &mut piece.state && position.x == 7 {
+            *cooldown = 5;
+        }
+    });
+    let black_board = BoardState::parse_fen("r3k2r/8/8/8/8/8/8/8").unwrap();
+    let results = [
+        board.validate_move(&BoardMove::LongCastle(Side::White)),
+        board.validate_move(&BoardMove::ShortCastle(Side::White)),
+        black_board.validate_move(&BoardMove::LongCastle(Side::Black)),
+    ]
+    .into_iter()
+    .map(|result| result.map_err(|error| error.to_string()))
+    .collect::<Vec<_>>();
+    expect!(
+        results,
+        r#"
+        [
+            Err(
+                "White cannot castle long because b1 is not empty",
+            ),
+            Err(
+                "White cannot castle short until the king and rook are ready",
+            ),
+            Err(
+                "Black is no longer allowed to castle long",
+            ),
+        ]"#
+    );
+}
+
+#[test]
+fn test_undo_restores_board() {
+    let mut board = BoardState::new_initial_state();
+    for i in 0..60 {
+        let white_moves = board.get_all_possible_moves(Side::White);
+        let black_moves = board.get_all_possible_moves(Side::Black);
+        let white_move = &white_moves[(i * 7) % white_moves.len()];
+        let black_move = &black_moves[(i * 3) % black_moves.len()];
+        // the piece order matters to move generation, so compare more than equality
+        let before = board.clone();
+        let undo = board.step_reversibly(white_move, black_move);
+        board.undo(undo);
+        assert_eq!(format!("{board:?}"), format!("{before:?}"));
+        let undo = board.apply_reversibly(|board| {
+            board.apply_move(white_move);
+            board.apply_move(black_move);
+            board.step_until_stationary_with_no_cooldown();
+        });
+        board.undo(undo);
+        assert_eq!(format!("{board:?}"), format!("{before:?}"));
+        board.step(white_move, black_move);
+    }
+    // make sure captures were undone along the way
+    assert!(board.pieces().len() < 32);
+}
@@ -1,8 +1,9 @@
 core!();
 
-use itertools::Itertools;
+use std::collections::HashSet;
 
-use crate::minimax;
+use itertools::Itertools;
+use rand::{rngs::StdRng, SeedableRng};
 
 use super::*;
 
@@ -18,6 +19,7 @@ fn visualize_moves_of_piece(piece_position: Position, moves: Vec<BoardMove>) ->
             if let BoardMove::Normal {
                 piece: Piece { state, .. },
                 target,
+                ..
             } = board_move && let PieceState::Stationary { position, .. } = state
             {
                 if *position == piece_position {
@@ -294,14 +296,14 @@ fn test_initial_possible_moves() {
 fn test_random_state_possible_moves_naive() {
     let moves = RANDOM_BOARD.get_all_possible_moves_naive(Side::White);
     let num_moves_per_type = num_moves_per_type(&moves);
-    expect!(moves.len(), "39");
+    expect!(moves.len(), "42");
     expect!(
         num_moves_per_type,
         r#"
         [
             (
                 Pawn,
-                4,
+                7,
             ),
             (
                 Knight,
@@ -363,7 +365,7 @@ fn test_quiescent_prioritize_taking_queen() {
         }
     });
     let quiescent_moves =
-        board.get_sorted_quiescent_moves(Side::White, |kind| minimax::MATERIAL_VALUE[kind] as i32);
+        board.get_sorted_quiescent_moves(Side::White, |kind| MATERIAL_VALUE[kind] as i32);
     expect!(
         quiescent_moves,
         r#"
@@ -384,6 +386,7 @@ fn test_quiescent_prioritize_taking_queen() {
                     x: 3,
                     y: 3,
                 },
+                promotion: None,
             },
             Normal {
                 piece: Piece {
@@ -401,6 +404,7 @@ fn test_quiescent_prioritize_taking_queen() {
                     x: 5,
                     y: 3,
                 },
+                promotion: None,
             },
             None(
                 White,
@@ -409,13 +413,19 @@ fn test_quiescent_prioritize_taking_queen() {
     );
 }
 
-// TODO-someday: need to extract the rng out so this test is repeatable
-// #[test]
-// fn test_generate_random_board_with() {
-//     let board = BoardState::generate_random_board_with(10);
-//     println!("{}", board.to_stationary_map_combo());
-//     expect!(board.to_stationary_map_combo());
-// }
+#[test]
+fn test_generate_random_board_with_num_pieces() {
+    let board =
+        BoardState::generate_random_board_with_num_pieces_rng(&mut StdRng::seed_from_u64(7), 10);
+    expect!(
+        board.to_stationary_map_combo(),
+        r#""..B...n.\np..p.P.K\np..P.P..\np..ppP.P\n.......N\n........\n...N...n\n.r...Rk.""#
+    );
+    // the same seed always gives the same board
+    let same_board =
+        BoardState::generate_random_board_with_num_pieces_rng(&mut StdRng::seed_from_u64(7), 10);
+    assert_eq!(same_board.to_fen(), board.to_fen());
+}
 
 #[test]
 fn test_initial_board_fen() {
@@ -429,3 +439,245 @@ fn test_initial_board_fen() {
         )"#
     );
 }
+
+#[test]
+fn test_initial_board_extended_fen() {
+    let board = BoardState::new_initial_state();
+    expect!(
+        board.to_fen(),
+        r#""rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR KQkq - -""#
+    );
+}
+
+#[test]
+fn test_plain_fen_is_extended_fen() {
+    let board = BoardState::parse_fen("3N4/b3P3/5p1B/2Q2bPP/PnK5/r5N1/7k/3r4").unwrap();
+    expect!(
+        board.to_fen(),
+        r#""3N4/b3P3/5p1B/2Q2bPP/PnK5/r5N1/7k/3r4 - - -""#
+    );
+}
+
+#[test]
+fn test_extended_fen_round_trip_with_moving_pieces() {
+    let mut board = RANDOM_BOARD.clone();
+    let queen_move = BoardMove::Normal {
+        piece: *board
+            .pieces()
+            .iter()
+            .find(|piece| piece.kind == PieceKind::Queen)
+            .unwrap(),
+        target: (5u32, 3u32).into(),
+        promotion: None,
+    };
+    assert!(board.can_move(&queen_move));
+    let knight_move = board
+        .get_all_possible_moves(Side::Black)
+        .into_iter()
+        .find(|board_move| {
+            matches!(board_move, BoardMove::Normal { piece, .. } if piece.kind == PieceKind::Knight)
+        })
+        .unwrap();
+    board.step(&queen_move, &knight_move);
+    board.step_without_moves();
+    let fen = board.to_fen();
+    expect!(
+        fen,
+        r#""3N4/b3P3/5p1B/3n1bPP/P1K5/r5N1/7k/3r4 - d5:10 Q:4:3:f5:1:2:1:0""#
+    );
+    let parsed = BoardState::parse_fen(&fen).unwrap();
+    assert_eq!(parsed.to_fen(), fen);
+    let sorted_pieces = |board: &BoardState| {
+        board
+            .pieces()
+            .iter()
+            .map(|piece| format!("{piece:?}"))
+            .sorted()
+            .collect_vec()
+    };
+    assert_eq!(sorted_pieces(&parsed), sorted_pieces(&board));
+}
+
+#[test]
+fn test_extended_fen_round_trip_with_cooldowns_and_castling() {
+    let mut board = BoardState::new_initial_state();
+    let white_move = board
+        .get_all_possible_moves(Side::White)
+        .into_iter()
+        .next()
+        .unwrap();
+    board.step(&white_move, &BoardMove::None(Side::Black));
+    board.step_n(3);
+    let fen = board.to_fen();
+    expect!(
+        fen,
+        r#""rnbqkbnr/pppppppp/8/8/P7/8/1PPPPPPP/RNBQKBNR KQkq a4:8 -""#
+    );
+    assert_eq!(BoardState::parse_fen(&fen).unwrap().to_fen(), fen);
+}
+
+#[test]
+fn test_extended_fen_round_trip_with_promotion() {
+    let mut board = BoardState::parse_fen("8/P7/8/8/8/8/8/8").unwrap();
+    let white_move = board
+        .get_all_possible_moves(Side::White)
+        .into_iter()
+        .find(|board_move| {
+            matches!(
+                board_move,
+                BoardMove::Normal {
+                    promotion: Some(PieceKind::Bishop),
+                    ..
+                }
+            )
+        })
+        .unwrap();
+    board.apply_move(&white_move);
+    let fen = board.to_fen();
+    expect!(fen, r#""8/8/8/8/8/8/8/8 - - P:0:1:a8:1:0:0:-1:B""#);
+    let mut parsed = BoardState::parse_fen(&fen).unwrap();
+    assert_eq!(parsed.to_fen(), fen);
+    parsed.step_without_moves();
+    expect!(
+        parsed.to_stationary_map_combo(),
+        r#""B.......\n........\n........\n........\n........\n........\n........\n........""#
+    );
+}
+
+#[test]
+fn test_extended_fen_round_trip_with_config() {
+    let configs = [
+        GameConfig::lightning(),
+        GameConfig::slow().with_ticks_per_square(PieceKind::Knight, 3),
+        GameConfig::standard().with_promotion(PromotionRule::Never),
+    ];
+    let fens = configs
+        .map(|config| {
+            let mut board = BoardState::parse_fen("4k3/8/8/8/8/8/8/4K1N1")
+                .unwrap()
+                .with_config(config);
+            let white_move = board.parse_move("Wg1f3").unwrap();
+            board.step(&white_move, &BoardMove::None(Side::Black));
+            let fen = board.to_fen();
+            let parsed = BoardState::parse_fen(&fen).unwrap();
+            assert_eq!(*parsed.config(), config);
+            assert_eq!(parsed.to_fen(), fen);
+            fen
+        });
+    expect!(
+        fens,
+        r#"
+        [
+            "4k3/8/8/8/8/8/8/4K3 - - N:5.5:6:f3:1:1:-0.5:-1 cooldown:2",
+            "4k3/8/8/8/8/8/8/4K3 - - N:5.8333335:6.6666665:f3:5:1:-0.16666667:-0.33333334 ticks:P:2,ticks:N:3,ticks:B:2,ticks:R:2,ticks:Q:2,ticks:K:2",
+            "4k3/8/8/8/8/8/8/4K3 - - N:5.5:6:f3:1:1:-0.5:-1 promotion:never",
+        ]"#
+    );
+}
+
+#[test]
+fn test_extended_fen_errors() {
+    expect!(
+        BoardState::parse_fen("8/8/8/8/8/8/8/8 X - -").map(|board| board.to_fen()),
+        r#"
+        Err(
+            "Unable to parse castling rights X",
+        )"#
+    );
+    expect!(
+        BoardState::parse_fen("8/8/8/8/8/8/8/8 - e4:3 -").map(|board| board.to_fen()),
+        r#"
+        Err(
+            "No stationary piece for cooldown e4:3",
+        )"#
+    );
+    expect!(
+        BoardState::parse_fen("8/8/8/8/8/8/8/8 - - Q:1:1:z9:1:0:0:0").map(|board| board.to_fen()),
+        r#"
+        Err(
+            "Unable to parse square z9",
+        )"#
+    );
+    expect!(
+        BoardState::parse_fen("8/8/8/8/8/8/8/8 - - - ticks:N:0").map(|board| board.to_fen()),
+        r#"
+        Err(
+            "Ticks per square must be at least 1 in ticks:N:0",
+        )"#
+    );
+}
+
+#[test]
+fn test_possible_moves_equivalent_to_naive_while_playing() {
+    fn to_sorted_debug(moves: Vec<BoardMove>) -> Vec<String> {
+        moves
+            .iter()
+            .map(|board_move| format!("{board_move:?}"))
+            .sorted()
+            .collect_vec()
+    }
+    let mut board = RANDOM_BOARD.clone();
+    for i in 0..50 {
+        let white_moves = board.get_all_possible_moves(Side::White);
+        let black_moves = board.get_all_possible_moves(Side::Black);
+        assert_eq!(
+            to_sorted_debug(white_moves.clone()),
+            to_sorted_debug(board.get_all_possible_moves_naive(Side::White))
+        );
+        assert_eq!(
+            to_sorted_debug(black_moves.clone()),
+            to_sorted_debug(board.get_all_possible_moves_naive(Side::Black))
+        );
+        let white_move = &white_moves[(i * 7) % white_moves.len()];
+        let black_move = &black_moves[(i * 5) % black_moves.len()];
+        board.step(white_move, black_move);
+    }
+}
+
+#[test]
+fn test_zobrist_hash_matches_recomputed_hash_while_playing() {
+    let mut board = BoardState::new_initial_state();
+    for i in 0..60 {
+        let white_moves = board.get_all_possible_moves(Side::White);
+        let black_moves = board.get_all_possible_moves(Side::Black);
+        board.step(
+            &white_moves[(i * 5) % white_moves.len()],
+            &black_moves[(i * 3) % black_moves.len()],
+        );
+        // parsing rebuilds the hash from scratch
+        let parsed = BoardState::parse_fen(&board.to_fen()).unwrap();
+        assert_eq!(parsed.zobrist_hash(), board.zobrist_hash());
+        assert_eq!(parsed, board);
+    }
+}
+
+#[test]
+fn test_board_equality() {
+    let board = BoardState::new_initial_state();
+    let mut reversed = board.clone();
+    reversed.pieces_mut().reverse();
+    assert_eq!(reversed, board);
+    assert_eq!(reversed.zobrist_hash(), board.zobrist_hash());
+
+    let mut moved = board.clone();
+    let white_move = moved.get_all_possible_moves(Side::White)[0].clone();
+    moved.apply_move(&white_move);
+    assert_ne!(moved, board);
+    assert_ne!(moved.zobrist_hash(), board.zobrist_hash());
+
+    let mut cooldown = board.clone();
+    if let PieceState::Stationary { cooldown, .. } = &mut cooldown.pieces_mut()[0].state {
+        *cooldown = 3;
+    }
+    assert_ne!(cooldown, board);
+    assert_ne!(cooldown.zobrist_hash(), board.zobrist_hash());
+
+    let no_castling = BoardState::parse_fen(&board.to_fen().replace("KQkq", "Kq")).unwrap();
+    assert_ne!(no_castling, board);
+    assert_ne!(no_castling.zobrist_hash(), board.zobrist_hash());
+
+    let boards: HashSet<BoardState> = [board.clone(), reversed, moved, cooldown, board]
+        .into_iter()
+        .collect();
+    assert_eq!(boards.len(), 3);
+}
@@ -0,0 +1,98 @@
+core!();
+
+use super::*;
+
+const LAST_INDEX: u32 = BOARD_SIZE as u32 - 1;
+
+// without turning a velocity of 0 into -0, which would be written and hashed differently
+fn negate(value: f32) -> f32 {
+    0f32 - value
+}
+
+impl BoardState {
+    // the same position with white and black swapped and the ranks mirrored, so that evaluations
+    // of the flipped board should be the negation of the original ones. searches are not
+    // symmetric: white always commits to a move before black answers, so flipping the board
+    // changes which side gets to answer
+    pub fn flip_colors(&self) -> Self {
+        let pieces = self.transform_pieces(
+            |side| side.opposite(),
+            |Position { x, y }| Position {
+                x,
+                y: LAST_INDEX - y,
+            },
+            |(x, y)| (x, LAST_INDEX as f32 - y),
+            |(vx, vy)| (vx, negate(vy)),
+        );
+        BoardState::new(
+            pieces,
+            enum_map! { side => self.can_long_castle(side.opposite()) },
+            enum_map! { side => self.can_short_castle(side.opposite()) },
+            *self.castling_files(),
+        )
+        .with_config(*self.config())
+    }
+
+    // the same position with files a to h mirrored, so that evaluations should not change.
+    // castling always takes the king to the c or g file, which does not mirror, so the rights
+    // are dropped
+    pub fn mirror_files(&self) -> Self {
+        let pieces = self.transform_pieces(
+            |side| side,
+            |Position { x, y }| Position {
+                x: LAST_INDEX - x,
+                y,
+            },
+            |(x, y)| (LAST_INDEX as f32 - x, y),
+            |(vx, vy)| (negate(vx), vy),
+        );
+        BoardState::new_with_castling(pieces, false).with_config(*self.config())
+    }
+
+    // maps the side, stationary positions, moving coordinates and velocity of every piece
+    fn transform_pieces<S, P, C, V>(
+        &self,
+        side: S,
+        position: P,
+        coordinates: C,
+        velocity: V,
+    ) -> Vec<Piece>
+    where
+        S: Fn(Side) -> Side,
+        P: Fn(Position) -> Position,
+        C: Fn((f32, f32)) -> (f32, f32),
+        V: Fn((f32, f32)) -> (f32, f32),
+    {
+        self.pieces()
+            .iter()
+            .map(|piece| {
+                let state = match piece.state {
+                    PieceState::Stationary {
+                        position: stationary_position,
+                        cooldown,
+                    } => PieceState::Stationary {
+                        position: position(stationary_position),
+                        cooldown,
+                    },
+                    PieceState::Moving { x, y, target } => {
+                        let (x, y) = coordinates((x, y));
+                        PieceState::Moving {
+                            x,
+                            y,
+                            target: MoveTarget {
+                                target: position(target.target),
+                                velocity: velocity(target.velocity),
+                                ..target
+                            },
+                        }
+                    }
+                };
+                Piece {
+                    side: side(piece.side),
+                    kind: piece.kind,
+                    state,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,59 @@
+core!();
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::*;
+
+fn generate_boards(seed: u64) -> Vec<BoardState> {
+    let constraints = PositionConstraints::midgame().with_moving_chance(0.3);
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..50)
+        .map(|_| BoardState::generate_constrained_board_rng(&mut rng, &constraints).unwrap())
+        .collect_vec()
+}
+
+#[test]
+fn test_flip_colors() {
+    let board = BoardState::parse_fen("r3k2r/8/8/8/8/8/8/R2QK3 Qk d1:3 R:7:6:h4:2:0:0:-1").unwrap();
+    expect!(
+        board.to_fen(),
+        r#""r3k2r/8/8/8/8/8/8/R2QK3 Qk d1:3 R:7:6:h4:2:0:0:-1""#
+    );
+    expect!(
+        board.flip_colors().to_fen(),
+        r#""r2qk3/8/8/8/8/8/8/R3K2R Kq d8:3 r:7:1:h5:2:0:0:1""#
+    );
+}
+
+#[test]
+fn test_mirror_files() {
+    let board = BoardState::parse_fen("r3k2r/8/8/8/8/8/8/R2QK3 Qk d1:3 R:7:6:h4:2:0:0:-1").unwrap();
+    expect!(
+        board.mirror_files().to_fen(),
+        r#""r2k3r/8/8/8/8/8/8/3KQ2R - e1:3 R:0:6:a4:2:0:0:-1""#
+    );
+}
+
+#[test]
+fn test_transforms_are_involutions() {
+    for board in generate_boards(5) {
+        assert_eq!(board.flip_colors().flip_colors(), board);
+        assert_eq!(board.mirror_files().mirror_files(), board);
+    }
+    let board = BoardState::new_initial_state();
+    assert_eq!(board.flip_colors(), board);
+}
+
+#[test]
+fn test_transforms_commute_with_step() {
+    for board in generate_boards(6) {
+        for transform in [BoardState::flip_colors, BoardState::mirror_files] {
+            let mut stepped = board.clone();
+            stepped.step_without_moves();
+            let mut transformed = transform(&board);
+            transformed.step_without_moves();
+            assert_eq!(transform(&stepped), transformed, "{}", board.to_fen());
+        }
+    }
+}
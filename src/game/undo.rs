@@ -0,0 +1,23 @@
+core!();
+
+use super::*;
+
+// A change to BoardState::pieces, recorded while a reversible step is running
+#[derive(Debug, Copy, Clone)]
+pub(super) enum PieceChange {
+    // the piece at index was overwritten, and this is how it was before
+    Changed { index: usize, piece: Piece },
+    // the piece was captured, index is where it goes back once the later changes are undone
+    Captured { index: usize, piece: Piece },
+}
+
+// Everything BoardState::undo needs to bring a board back to how it was before
+// BoardState::step_reversibly (or apply_reversibly), without having to clone the board
+#[derive(Debug, Clone)]
+pub struct StepUndo {
+    pub(super) changes: Vec<PieceChange>,
+    pub(super) can_long_castle: EnumMap<Side, bool>,
+    pub(super) can_short_castle: EnumMap<Side, bool>,
+    pub(super) occupancy: Occupancy,
+    pub(super) hash: ZobristHash,
+}
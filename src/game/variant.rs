@@ -0,0 +1,214 @@
+core!();
+
+use super::*;
+use itertools::Itertools;
+use rand::Rng;
+
+// A named starting setup, so that games do not all have to begin from the usual position
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub name: String,
+    pub initial_state: BoardState,
+}
+
+pub const NUM_CHESS960_SETUPS: u32 = 960;
+pub const STANDARD_CHESS960_ID: u32 = 518; // RNBQKBNR
+
+// where the two knights go among the five files left once the bishops and the queen are placed
+const CHESS960_KNIGHT_FILES: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+impl Variant {
+    pub fn standard() -> Self {
+        Self {
+            name: "standard".to_owned(),
+            initial_state: BoardState::new_initial_state(),
+        }
+    }
+    pub fn chess960(id: u32) -> OrError<Self> {
+        Ok(Self {
+            name: format!("chess960-{id}"),
+            initial_state: BoardState::from_back_rank(&chess960_back_rank(id)?)?,
+        })
+    }
+    pub fn random_chess960<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::chess960(rng.gen_range(0..NUM_CHESS960_SETUPS)).unwrap()
+    }
+    pub fn from_fen(name: &str, fen: &str) -> OrError<Self> {
+        Ok(Self {
+            name: name.to_owned(),
+            initial_state: BoardState::parse_fen(fen)?,
+        })
+    }
+    pub fn from_diagram(name: &str, diagram: &str) -> OrError<Self> {
+        Ok(Self {
+            name: name.to_owned(),
+            initial_state: BoardState::parse_diagram(diagram)?,
+        })
+    }
+}
+
+// back ranks are numbered like in Chess960 (from a to h, see STANDARD_CHESS960_ID)
+pub fn chess960_back_rank(id: u32) -> OrError<[PieceKind; BOARD_SIZE]> {
+    if id >= NUM_CHESS960_SETUPS {
+        return Err(Error!(
+            "Chess960 setups are numbered from 0 to {}, not {}",
+            NUM_CHESS960_SETUPS - 1,
+            id
+        ));
+    }
+    let mut back_rank = [None; BOARD_SIZE];
+    let mut id = id as usize;
+    // one bishop on a light square and one on a dark square
+    back_rank[(id % 4) * 2 + 1] = Some(PieceKind::Bishop);
+    id /= 4;
+    back_rank[(id % 4) * 2] = Some(PieceKind::Bishop);
+    id /= 4;
+    let empty_files = |back_rank: &[Option<PieceKind>]| {
+        back_rank
+            .iter()
+            .positions(|kind| kind.is_none())
+            .collect_vec()
+    };
+    back_rank[empty_files(&back_rank)[id % 6]] = Some(PieceKind::Queen);
+    id /= 6;
+    let empty = empty_files(&back_rank);
+    let (first_knight, second_knight) = CHESS960_KNIGHT_FILES[id];
+    back_rank[empty[first_knight]] = Some(PieceKind::Knight);
+    back_rank[empty[second_knight]] = Some(PieceKind::Knight);
+    // the king always ends up between the rooks
+    let empty = empty_files(&back_rank);
+    for (file, kind) in empty
+        .into_iter()
+        .zip([PieceKind::Rook, PieceKind::King, PieceKind::Rook])
+    {
+        back_rank[file] = Some(kind);
+    }
+    Ok(back_rank.map(Option::unwrap))
+}
+
+impl BoardState {
+    // the back rank from a to h for both sides, with pawns in front of it
+    pub fn from_back_rank(back_rank: &[PieceKind; BOARD_SIZE]) -> OrError<Self> {
+        let white_back_rank: String = back_rank.iter().map(|&kind| char::from(kind)).collect();
+        let empty_rank = ".".repeat(BOARD_SIZE);
+        let diagram = [
+            white_back_rank.to_ascii_lowercase(),
+            "p".repeat(BOARD_SIZE),
+            empty_rank.clone(),
+            empty_rank.clone(),
+            empty_rank.clone(),
+            empty_rank,
+            "P".repeat(BOARD_SIZE),
+            white_back_rank,
+        ]
+        .join("\n");
+        Self::parse_diagram(&diagram)
+    }
+    // one line per rank from the 8th to the 1st, with FEN letters for pieces and '.' for empty
+    // squares (blank lines and indentation are ignored, ex: the output of to_stationary_map_combo)
+    // castling is allowed with the outermost rook on each side of a king on its back rank,
+    // for black only when it starts on the same files as white
+    pub fn parse_diagram(diagram: &str) -> OrError<Self> {
+        let ranks = diagram
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect_vec();
+        if ranks.len() != BOARD_SIZE || ranks.iter().any(|rank| rank.chars().count() != BOARD_SIZE)
+        {
+            return Err(Error!(
+                "Diagram should have {} ranks of {} squares: {}",
+                BOARD_SIZE,
+                BOARD_SIZE,
+                diagram
+            ));
+        }
+        let placement = ranks
+            .iter()
+            .map(|rank| {
+                rank.chars()
+                    .group_by(|&c| c == '.')
+                    .into_iter()
+                    .map(|(is_empty, group)| {
+                        if is_empty {
+                            group.count().to_string()
+                        } else {
+                            group.collect()
+                        }
+                    })
+                    .join("")
+            })
+            .join("/");
+        let board = Self::parse_fen(&placement)?;
+        // (king file, outermost rook file on the long side, and on the short side)
+        let castling_files = |side: Side| {
+            let back_rank_files = |kind: PieceKind| {
+                board
+                    .pieces()
+                    .iter()
+                    .filter_map(move |piece| match piece.state {
+                        PieceState::Stationary { position, .. }
+                            if piece.side == side
+                                && piece.kind == kind
+                                && position.y == back_rank_y(side) =>
+                        {
+                            Some(position.x)
+                        }
+                        _ => None,
+                    })
+            };
+            let king = back_rank_files(PieceKind::King).next()?;
+            let long_rook = back_rank_files(PieceKind::Rook).filter(|&x| x < king).min();
+            let short_rook = back_rank_files(PieceKind::Rook).filter(|&x| x > king).max();
+            Some((king, long_rook, short_rook))
+        };
+        let white = castling_files(Side::White);
+        let black = castling_files(Side::Black).map(|(king, long_rook, short_rook)| {
+            let Some((white_king, white_long_rook, white_short_rook)) = white else {
+                return (king, long_rook, short_rook);
+            };
+            let agrees = |rook: Option<u32>, white_rook: Option<u32>| {
+                rook.filter(|&rook| {
+                    king == white_king && white_rook.map_or(true, |white_rook| white_rook == rook)
+                })
+            };
+            (
+                king,
+                agrees(long_rook, white_long_rook),
+                agrees(short_rook, white_short_rook),
+            )
+        });
+        // written as the files of the rooks, like parse_fen expects for Chess960
+        let castling: String = [(Side::White, white), (Side::Black, black)]
+            .into_iter()
+            .flat_map(|(side, files)| {
+                let (_, long_rook, short_rook) = files.unwrap_or((0, None, None));
+                [short_rook, long_rook]
+                    .into_iter()
+                    .flatten()
+                    .map(move |file| {
+                        let c = (b'a' + file as u8) as char;
+                        match side {
+                            Side::White => c.to_ascii_uppercase(),
+                            Side::Black => c,
+                        }
+                    })
+            })
+            .collect();
+        if castling.is_empty() {
+            return Ok(board);
+        }
+        Self::parse_fen(&format!("{placement} {castling} - -"))
+    }
+}
@@ -0,0 +1,174 @@
+core!();
+
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use super::*;
+
+fn back_rank_string(id: u32) -> String {
+    chess960_back_rank(id)
+        .unwrap()
+        .into_iter()
+        .map(char::from)
+        .collect()
+}
+
+fn snapshots_until_stationary(board: &mut BoardState) -> Vec<String> {
+    let mut all_steps = vec![board.to_stationary_map_combo()];
+    while !board.is_all_pieces_stationary() {
+        board.step_without_moves();
+        all_steps.push(board.to_stationary_map_combo());
+    }
+    all_steps
+}
+
+#[test]
+fn test_chess960_back_ranks() {
+    let back_ranks = [0, STANDARD_CHESS960_ID, NUM_CHESS960_SETUPS - 1].map(back_rank_string);
+    expect!(
+        back_ranks,
+        r#"
+        [
+            "BBQNNRKR",
+            "RNBQKBNR",
+            "RKRNNQBB",
+        ]"#
+    );
+    expect!(
+        chess960_back_rank(NUM_CHESS960_SETUPS).map_err(|e| e.to_string()),
+        r#"
+        Err(
+            "Chess960 setups are numbered from 0 to 959, not 960",
+        )"#
+    );
+}
+
+#[test]
+fn test_all_chess960_setups_are_valid() {
+    let back_ranks = (0..NUM_CHESS960_SETUPS)
+        .map(back_rank_string)
+        .collect::<HashSet<_>>();
+    assert_eq!(back_ranks.len(), NUM_CHESS960_SETUPS as usize);
+    for back_rank in back_ranks {
+        let files = |kind: char| back_rank.match_indices(kind).map(|(x, _)| x).collect_vec();
+        let (bishops, rooks, king) = (files('B'), files('R'), files('K'));
+        assert_ne!(bishops[0] % 2, bishops[1] % 2, "{back_rank}");
+        assert!(rooks[0] < king[0] && king[0] < rooks[1], "{back_rank}");
+    }
+}
+
+#[test]
+fn test_standard_chess960_is_initial_state() {
+    let variant = Variant::chess960(STANDARD_CHESS960_ID).unwrap();
+    assert_eq!(variant.initial_state, BoardState::new_initial_state());
+    assert_eq!(
+        variant.initial_state.to_fen(),
+        BoardState::new_initial_state().to_fen()
+    );
+}
+
+#[test]
+fn test_chess960_castle() {
+    // king on f, rooks on e and g, with nothing else on white's back rank
+    let diagram = "
+        bqnbrkrn
+        pppppppp
+        ........
+        ........
+        ........
+        ........
+        PPPPPPPP
+        ....RKR.
+    ";
+    let mut board = Variant::from_diagram("custom", diagram)
+        .unwrap()
+        .initial_state;
+    assert_eq!(
+        board.castling_files(),
+        &CastlingFiles {
+            king: 5,
+            long_rook: 4,
+            short_rook: 6,
+        }
+    );
+    expect!(
+        board.to_fen(),
+        r#""bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/4RKR1 GEge - -""#
+    );
+    board.apply_move(&BoardMove::ShortCastle(Side::White));
+    expect!(
+        snapshots_until_stationary(&mut board),
+        r#"
+        [
+            "bqnbrkrn\npppppppp\n........\n........\n........\n........\nPPPPPPPP\n....R...",
+            "bqnbrkrn\npppppppp\n........\n........\n........\n........\nPPPPPPPP\n....RRK.",
+        ]"#
+    );
+}
+
+#[test]
+fn test_chess960_fen_round_trip() {
+    let fen = "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/4RKR1 GEge - -";
+    let board = BoardState::parse_fen(fen).unwrap();
+    assert_eq!(board.to_fen(), fen);
+    expect!(
+        ["GEgb", "Ge"].map(|castling| {
+            BoardState::parse_fen(&format!("bqnbrkrn/8/8/8/8/8/8/BQNBRKRN {castling} - -"))
+                .map(|board| board.to_fen())
+                .map_err(|e| e.to_string())
+        }),
+        r#"
+        [
+            Err(
+                "Unable to parse castling rights GEgb",
+            ),
+            Ok(
+                "bqnbrkrn/8/8/8/8/8/8/BQNBRKRN Ge - -",
+            ),
+        ]"#
+    );
+}
+
+#[test]
+fn test_castling_files_change_the_hash() {
+    // the same pieces, but castling long with the rook on a or on b
+    let boards = ["A", "B"].map(|castling| {
+        BoardState::parse_fen(&format!("4k3/8/8/8/8/8/8/RR2K3 {castling} - -")).unwrap()
+    });
+    expect!(
+        boards
+            .iter()
+            .map(|board| board.castling_files())
+            .collect_vec(),
+        r#"
+        [
+            CastlingFiles {
+                king: 4,
+                long_rook: 0,
+                short_rook: 7,
+            },
+            CastlingFiles {
+                king: 4,
+                long_rook: 1,
+                short_rook: 7,
+            },
+        ]"#
+    );
+    assert_ne!(boards[0], boards[1]);
+    assert_ne!(boards[0].zobrist_hash(), boards[1].zobrist_hash());
+}
+
+#[test]
+fn test_bad_diagram() {
+    let diagram = "
+        rnbqkbnr
+        pppppppp
+        ........
+        PPPPPPPP
+        RNBQKBNR
+    ";
+    assert!(Variant::from_diagram("short", diagram).is_err());
+    let diagram = diagram.replace("PPPPPPPP", "PPPPPPPX");
+    assert!(BoardState::parse_diagram(&diagram).is_err());
+}
@@ -0,0 +1,97 @@
+core!();
+
+use super::*;
+use enum_map::Enum;
+
+// Zobrist hashing: the hash of a board is the xor of one key per piece and per castling right,
+// so it can be updated by xor-ing out the old key and xor-ing in the new one.
+// Pieces carry too many values (cooldowns, priorities, positions of moving pieces) for a table
+// of random keys, so each key is derived by mixing the values instead.
+pub type ZobristHash = u64;
+
+// splitmix64 finalizer
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+fn combine(values: &[u64]) -> ZobristHash {
+    values
+        .iter()
+        .fold(0x9e3779b97f4a7c15, |hash, &value| mix(hash ^ value))
+}
+
+// for keys built on top of board hashes (ex: search caches), with the same mixing as the pieces
+pub fn combine_keys(values: &[u64]) -> ZobristHash {
+    combine(values)
+}
+
+fn square_index(position: Position) -> u64 {
+    (position.y as u64) * (BOARD_SIZE as u64) + (position.x as u64)
+}
+
+// every value that identifies a piece, packed into as few words as possible to keep hashing cheap
+// (the last three words are only used by moving pieces)
+pub(super) fn piece_values(piece: &Piece) -> [u64; 4] {
+    let side = Side::into_usize(piece.side) as u64;
+    let kind = PieceKind::into_usize(piece.kind) as u64;
+    let pack = |is_moving: u64, square: Position, promotion: Option<PieceKind>, value: u32| {
+        let promotion = promotion.map_or(0, |kind| 1 + PieceKind::into_usize(kind) as u64);
+        is_moving
+            | (side << 1)
+            | (kind << 2)
+            | (square_index(square) << 5)
+            | (promotion << 11)
+            | ((value as u64) << 32)
+    };
+    let pack_pair = |a: u32, b: u32| (a as u64) | ((b as u64) << 32);
+    match piece.state {
+        PieceState::Stationary { position, cooldown } => {
+            [pack(0, position, None, cooldown), 0, 0, 0]
+        }
+        PieceState::Moving {
+            x,
+            y,
+            target:
+                MoveTarget {
+                    target,
+                    turns_left,
+                    priority,
+                    velocity: (vx, vy),
+                    promotion,
+                },
+        } => [
+            pack(1, target, promotion, turns_left),
+            pack_pair(priority, 0),
+            pack_pair(x.to_bits(), y.to_bits()),
+            pack_pair(vx.to_bits(), vy.to_bits()),
+        ],
+    }
+}
+
+pub fn piece_key(piece: &Piece) -> ZobristHash {
+    let values = piece_values(piece);
+    if piece.state.is_stationary() {
+        combine(&values[..1])
+    } else {
+        combine(&values)
+    }
+}
+
+// the files are part of the key, so boards that only differ in where castling starts from
+// (ex: in Chess960) hash differently as long as they can castle
+pub fn castling_key(side: Side, is_long_castle: bool, files: &CastlingFiles) -> ZobristHash {
+    let rook = if is_long_castle {
+        files.long_rook
+    } else {
+        files.short_rook
+    };
+    combine(&[
+        2,
+        Side::into_usize(side) as u64,
+        is_long_castle as u64,
+        files.king as u64,
+        rook as u64,
+    ])
+}
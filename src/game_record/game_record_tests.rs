@@ -0,0 +1,112 @@
+core!();
+
+use std::time::Duration;
+
+use super::*;
+
+// plays a fixed sequence of moves and keeps every state along the way
+fn play_recorded_game(num_ticks: usize) -> (GameRecord, Vec<BoardState>) {
+    play_recorded_game_from(BoardState::new_initial_state(), num_ticks)
+}
+
+fn play_recorded_game_from(
+    mut board: BoardState,
+    num_ticks: usize,
+) -> (GameRecord, Vec<BoardState>) {
+    let mut record = GameRecord::new(board.clone());
+    let mut states = vec![board.clone()];
+    for i in 0..num_ticks {
+        let white_moves = board.get_all_possible_moves(Side::White);
+        let black_moves = board.get_all_possible_moves(Side::Black);
+        let tick = RecordedTick {
+            white_move: white_moves[(i * 7) % white_moves.len()].clone(),
+            black_move: black_moves[(i * 3) % black_moves.len()].clone(),
+            white_time: Duration::from_micros(100 + i as u64),
+            black_time: Duration::from_micros(200 + i as u64),
+        };
+        board.step(&tick.white_move, &tick.black_move);
+        record.push_tick(tick);
+        states.push(board.clone());
+    }
+    record.finish(EndState::Draw, EndReason::MaxStepsReached);
+    (record, states)
+}
+
+#[test]
+fn test_game_record_text() {
+    let (record, _) = play_recorded_game(4);
+    expect!(
+        record.to_text().lines().collect::<Vec<_>>(),
+        r#"
+        [
+            "start rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR KQkq - -",
+            "tick Wa2a4 Ba7a5 100 200",
+            "tick We2e3 Bc7c6 101 201",
+            "tick Wg1e2 Bf7f5 102 202",
+            "tick Wc2c3 Bh7h6 103 203",
+            "end Draw MaxStepsReached",
+        ]"#
+    );
+    assert_eq!(record.total_time(Side::Black), Duration::from_micros(806));
+}
+
+#[test]
+fn test_game_record_round_trip_and_replay() {
+    let (record, states) = play_recorded_game(40);
+    let parsed = GameRecord::parse(&record.to_text()).unwrap();
+    assert_eq!(parsed.to_text(), record.to_text());
+    assert_eq!(
+        parsed.result,
+        Some((EndState::Draw, EndReason::MaxStepsReached))
+    );
+    assert_eq!(parsed.replay(), states);
+    assert_eq!(&record.final_state(), states.last().unwrap());
+}
+
+#[test]
+fn test_game_record_config_round_trip() {
+    let initial_state = BoardState::new_initial_state().with_config(GameConfig::lightning());
+    let (record, states) = play_recorded_game_from(initial_state, 20);
+    let text = record.to_text();
+    expect!(
+        text.lines().next(),
+        r#"
+        Some(
+            "start rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR KQkq - - cooldown:2",
+        )"#
+    );
+    let parsed = GameRecord::parse(&text).unwrap();
+    assert_eq!(parsed.initial_state.config(), &GameConfig::lightning());
+    assert_eq!(parsed.to_text(), text);
+    assert_eq!(parsed.replay(), states);
+}
+
+#[test]
+fn test_game_record_errors() {
+    let start = format!("start {}\n", BoardState::new_initial_state().to_fen());
+    let errors = [
+        "tick W- B- 0 0".to_owned(),
+        format!("{start}tick B- W- 0 0"),
+        format!("{start}tick Wa2a3 B- x 0"),
+        format!("{start}tick Wa3a4 B- 0 0"),
+        format!("{start}end Nobody KingCaptured"),
+        format!("{start}end White Resigned"),
+        format!("{start}end Draw MaxStepsReached\ntick W- B- 0 0"),
+    ]
+    .iter()
+    .map(|text| GameRecord::parse(text).unwrap_err().to_string())
+    .collect::<Vec<_>>();
+    expect!(
+        errors,
+        r#"
+        [
+            "Game record does not begin with a start line",
+            "Moves are not in white, black order in tick B- W- 0 0",
+            "Unable to parse time x in tick Wa2a3 B- x 0",
+            "No stationary White piece on a3 for move Wa3a4",
+            "Unable to parse winner Nobody in end Nobody KingCaptured",
+            "Unable to parse end reason Resigned in end White Resigned",
+            "Unexpected line after the end of the game: tick W- B- 0 0",
+        ]"#
+    );
+}
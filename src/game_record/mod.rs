@@ -0,0 +1,166 @@
+core!();
+
+use std::{fmt::Write as _, path::Path, time::Duration};
+
+use crate::*;
+
+#[cfg(test)]
+mod game_record_tests;
+
+// Everything needed to look back at a game: the starting state, the moves of both sides at
+// every tick, how long each side took to pick them and how the game ended.
+// The text form keeps the starting state as an extended FEN, which includes its config.
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub initial_state: BoardState,
+    pub ticks: Vec<RecordedTick>,
+    pub result: Option<(EndState, EndReason)>, // None while the game is still being played
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedTick {
+    pub white_move: BoardMove,
+    pub black_move: BoardMove,
+    pub white_time: Duration,
+    pub black_time: Duration,
+}
+
+impl GameRecord {
+    pub fn new(initial_state: BoardState) -> Self {
+        Self {
+            initial_state,
+            ticks: Vec::new(),
+            result: None,
+        }
+    }
+    pub fn push_tick(&mut self, tick: RecordedTick) {
+        debug_assert!(self.result.is_none());
+        debug_assert!(tick.white_move.side() == Side::White);
+        debug_assert!(tick.black_move.side() == Side::Black);
+        self.ticks.push(tick);
+    }
+    pub fn finish(&mut self, end_state: EndState, reason: EndReason) {
+        self.result = Some((end_state, reason));
+    }
+    pub fn end_state(&self) -> Option<EndState> {
+        self.result.map(|(end_state, _)| end_state)
+    }
+    pub fn total_time(&self, side: Side) -> Duration {
+        self.ticks
+            .iter()
+            .map(|tick| match side {
+                Side::White => tick.white_time,
+                Side::Black => tick.black_time,
+            })
+            .sum()
+    }
+    // the state before the first tick, then the state after every tick
+    pub fn replay(&self) -> Vec<BoardState> {
+        let mut board = self.initial_state.clone();
+        let mut states = vec![board.clone()];
+        for tick in self.ticks.iter() {
+            board.step(&tick.white_move, &tick.black_move);
+            states.push(board.clone());
+        }
+        states
+    }
+    pub fn final_state(&self) -> BoardState {
+        self.replay().pop().unwrap()
+    }
+    // one line per entry:
+    //   start <extended FEN>
+    //   tick <white move> <black move> <white microseconds> <black microseconds>
+    //   end <White|Black|Draw> <EndReason>
+    pub fn to_text(&self) -> String {
+        let mut text = format!("start {}\n", self.initial_state.to_fen());
+        for tick in self.ticks.iter() {
+            writeln!(
+                text,
+                "tick {} {} {} {}",
+                tick.white_move,
+                tick.black_move,
+                tick.white_time.as_micros(),
+                tick.black_time.as_micros()
+            )
+            .unwrap();
+        }
+        if let Some((end_state, reason)) = self.result {
+            let winner = match end_state {
+                EndState::Winner(side) => format!("{:?}", side),
+                EndState::Draw => "Draw".to_owned(),
+            };
+            writeln!(text, "end {} {:?}", winner, reason).unwrap();
+        }
+        text
+    }
+    // moves are resolved against the replayed state of their tick
+    pub fn parse(text: &str) -> OrError<Self> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let initial_state = lines
+            .next()
+            .and_then(|line| line.strip_prefix("start "))
+            .ok_or_else(|| Error!("Game record does not begin with a start line"))?;
+        let mut record = Self::new(BoardState::parse_fen(initial_state)?);
+        let mut board = record.initial_state.clone();
+        for line in lines {
+            if record.result.is_some() {
+                return Err(Error!(
+                    "Unexpected line after the end of the game: {}",
+                    line
+                ));
+            }
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields[..] {
+                ["tick", white_move, black_move, white_time, black_time] => {
+                    let parse_time = |time: &str| {
+                        time.parse::<u64>()
+                            .map(Duration::from_micros)
+                            .map_err(|_| Error!("Unable to parse time {} in {}", time, line))
+                    };
+                    let tick = RecordedTick {
+                        white_move: board.parse_move(white_move)?,
+                        black_move: board.parse_move(black_move)?,
+                        white_time: parse_time(white_time)?,
+                        black_time: parse_time(black_time)?,
+                    };
+                    if tick.white_move.side() != Side::White
+                        || tick.black_move.side() != Side::Black
+                    {
+                        return Err(Error!("Moves are not in white, black order in {}", line));
+                    }
+                    board.step(&tick.white_move, &tick.black_move);
+                    record.push_tick(tick);
+                }
+                ["end", winner, reason] => {
+                    let end_state = match winner {
+                        "White" => EndState::Winner(Side::White),
+                        "Black" => EndState::Winner(Side::Black),
+                        "Draw" => EndState::Draw,
+                        _ => return Err(Error!("Unable to parse winner {} in {}", winner, line)),
+                    };
+                    let reason = match reason {
+                        "KingCaptured" => EndReason::KingCaptured,
+                        "OnlyKingsLeft" => EndReason::OnlyKingsLeft,
+                        "InsufficientMaterial" => EndReason::InsufficientMaterial,
+                        "Repetition" => EndReason::Repetition,
+                        "NoProgressLimitReached" => EndReason::NoProgressLimitReached,
+                        "MaxStepsReached" => EndReason::MaxStepsReached,
+                        _ => {
+                            return Err(Error!("Unable to parse end reason {} in {}", reason, line))
+                        }
+                    };
+                    record.finish(end_state, reason);
+                }
+                _ => return Err(Error!("Unable to parse game record line {}", line)),
+            }
+        }
+        Ok(record)
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> OrError<()> {
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+    pub fn load(path: impl AsRef<Path>) -> OrError<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+}
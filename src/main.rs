@@ -1,7 +1,6 @@
 #![feature(const_for)]
 #![feature(let_chains)]
 #![feature(array_zip)]
-#![feature(variant_count)]
 
 macro_rules! core {
     () => {
@@ -16,7 +15,6 @@ mod imports;
 
 mod game;
 use std::{
-    cmp::Ordering,
     fmt::Display,
     fs::File,
     io::BufRead,
@@ -34,6 +32,12 @@ pub use minimax::*;
 mod board_representation;
 pub use board_representation::*;
 
+mod game_record;
+pub use game_record::*;
+
+mod svg;
+pub use svg::*;
+
 pub mod util;
 
 mod sequential;
@@ -42,7 +46,7 @@ use itertools::Itertools;
 
 use numpy::{ndarray::Array1, PyArray1, PyArray2};
 use pyo3::{prelude::*, types::PyModule};
-use rand::seq::SliceRandom;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
 use crate::{
     sequential::SequentialModel,
@@ -51,9 +55,16 @@ use crate::{
 
 const SEARCH_DEPTH: u32 = 2;
 
-fn random_move(board: &BoardState, side: Side) -> BoardMove {
+// every game has its own rng, derived from the seed and the index of the game, so that games
+// stay reproducible even though they are played in parallel. the two are mixed together so that
+// no game shares a stream with another seed or with the rng seeded by the seed itself
+fn game_rng(seed: u64, game_index: usize) -> StdRng {
+    StdRng::seed_from_u64(combine_keys(&[seed, game_index as u64]))
+}
+
+fn random_move(board: &BoardState, side: Side, rng: &mut StdRng) -> BoardMove {
     let all_moves = board.get_all_possible_moves(side);
-    all_moves.choose(&mut rand::thread_rng()).cloned().unwrap()
+    all_moves.choose(rng).cloned().unwrap()
 }
 
 fn evaluate_board_with_sequential(board: &BoardState, model: &SequentialModel) -> f32 {
@@ -69,19 +80,34 @@ fn evaluate_board_with_sequential(board: &BoardState, model: &SequentialModel) -
 fn move_from_minimax_with_sequential(
     board: &BoardState,
     side: Side,
+    progress: &GameProgress,
+    rules: &EndStateRules,
+    budget: SearchBudget,
     model: &SequentialModel,
 ) -> BoardMove {
-    search_white(board, SEARCH_DEPTH, |board| {
+    search_white_with_rules(board, budget, rules, progress, |board| {
         evaluate_board_with_sequential(board, model)
     })
     .unwrap()
     .get_first_move_of_side(side)
 }
 
-fn move_from_minimax_with_heuristic(board: &BoardState, side: Side) -> BoardMove {
-    search_white_with_heuristic(board, SEARCH_DEPTH)
-        .unwrap()
-        .get_first_move_of_side(side)
+fn move_from_minimax_with_heuristic(
+    board: &BoardState,
+    side: Side,
+    progress: &GameProgress,
+    rules: &EndStateRules,
+    budget: SearchBudget,
+) -> BoardMove {
+    search_white_with_rules(
+        board,
+        budget,
+        rules,
+        progress,
+        minimax::evaluate_material_heuristic,
+    )
+    .unwrap()
+    .get_first_move_of_side(side)
 }
 
 struct VersusStats {
@@ -115,57 +141,78 @@ impl Display for VersusStats {
 }
 
 // take some random boards, play one as white, one as black, all the way to the end, count wins/draws/losses of each player as white/black
+// save_games is an optional (directory, matchup name, epoch) to save every game to
 fn get_versus_stats<F, G>(
     boards: &[BoardState],
-    max_steps: usize,
+    rules: &EndStateRules,
+    seed: u64,
     player_a: F,
     player_b: G,
+    save_games: Option<(&str, &str, usize)>,
 ) -> VersusStats
 where
-    F: Fn(&BoardState, Side) -> BoardMove + Sync,
-    G: Fn(&BoardState, Side) -> BoardMove + Sync,
+    F: Fn(&BoardState, Side, &GameProgress, &mut StdRng) -> BoardMove + Sync,
+    G: Fn(&BoardState, Side, &GameProgress, &mut StdRng) -> BoardMove + Sync,
 {
+    let indexed_boards = boards.iter().cloned().enumerate().collect_vec();
     let (a_as_white, a_as_black, b_as_white, b_as_black): (
         Counter<_>,
         Counter<_>,
         Counter<_>,
         Counter<_>,
-    ) = parallel_map_prioritized_by_pieces(boards, |board| {
-        println!("Playing as white... {}", board.to_stationary_fen().unwrap());
-        let a = play_to_end_state(
-            board.clone(),
-            max_steps,
-            |board| player_a(board, Side::White),
-            |board| player_b(board, Side::Black),
-        );
-        println!("Playing as black... {}", board.to_stationary_fen().unwrap());
-        let b = play_to_end_state(
-            board.clone(),
-            max_steps,
-            |board| player_b(board, Side::White),
-            |board| player_a(board, Side::Black),
-        );
-        let outcome_a = match a {
-            EndState::Winner(side) => match side {
-                Side::White => Outcome::Win,
-                Side::Black => Outcome::Lose,
-            },
-            EndState::Draw => Outcome::Draw,
-        };
-        let outcome_b = match b {
-            EndState::Winner(side) => match side {
-                Side::White => Outcome::Lose,
-                Side::Black => Outcome::Win,
-            },
-            EndState::Draw => Outcome::Draw,
-        };
-        (
-            outcome_a,
-            outcome_b,
-            outcome_b.opposite(),
-            outcome_a.opposite(),
-        )
-    })
+    ) = parallel_map_prioritized_by(
+        &indexed_boards,
+        |(board_index, board)| {
+            println!("Playing as white... {}", board.to_fen());
+            let a = play_to_end_state(
+                board.clone(),
+                rules,
+                &mut game_rng(seed, 2 * board_index),
+                |board, progress, rng| player_a(board, Side::White, progress, rng),
+                |board, progress, rng| player_b(board, Side::Black, progress, rng),
+            );
+            println!("Playing as black... {}", board.to_fen());
+            let b = play_to_end_state(
+                board.clone(),
+                rules,
+                &mut game_rng(seed, 2 * board_index + 1),
+                |board, progress, rng| player_b(board, Side::White, progress, rng),
+                |board, progress, rng| player_a(board, Side::Black, progress, rng),
+            );
+            if let Some((dir, matchup, epoch)) = save_games {
+                for (record, white_player) in [(&a, "a"), (&b, "b")] {
+                    let path = format!(
+                        "{dir}/{matchup}-epoch{epoch}-board{board_index}-{white_player}-white.txt"
+                    );
+                    // a game that can't be saved shouldn't stop the others from being played
+                    if let Err(error) = record.save(&path) {
+                        println!("Unable to save game to {path}: {error}");
+                    }
+                }
+            }
+            let outcome_a = match a.end_state().unwrap() {
+                EndState::Winner(side) => match side {
+                    Side::White => Outcome::Win,
+                    Side::Black => Outcome::Lose,
+                },
+                EndState::Draw => Outcome::Draw,
+            };
+            let outcome_b = match b.end_state().unwrap() {
+                EndState::Winner(side) => match side {
+                    Side::White => Outcome::Lose,
+                    Side::Black => Outcome::Win,
+                },
+                EndState::Draw => Outcome::Draw,
+            };
+            (
+                outcome_a,
+                outcome_b,
+                outcome_b.opposite(),
+                outcome_a.opposite(),
+            )
+        },
+        |(_, board)| -(board.pieces().len() as i32), // order by descending number of pieces
+    )
     .into_iter()
     .multiunzip();
 
@@ -205,48 +252,55 @@ impl Outcome {
     }
 }
 
+// the game is only sure to end if rules has one of the step limits
 fn play_to_end_state<F, G>(
     mut board: BoardState,
-    max_steps: usize,
+    rules: &EndStateRules,
+    rng: &mut StdRng,
     mut white_player: F,
     mut black_player: G,
-) -> EndState
+) -> GameRecord
 where
-    F: FnMut(&BoardState) -> BoardMove,
-    G: FnMut(&BoardState) -> BoardMove,
+    F: FnMut(&BoardState, &GameProgress, &mut StdRng) -> BoardMove,
+    G: FnMut(&BoardState, &GameProgress, &mut StdRng) -> BoardMove,
 {
-    let mut current_step = 0;
-    let mut white_time = Duration::from_secs(0);
-    let mut black_time = Duration::from_secs(0);
+    let mut record = GameRecord::new(board.clone());
+    let mut progress = GameProgress::new(&board);
     let mut step_time = Duration::from_secs(0);
-    while current_step < max_steps {
-        if let Some(end_state) = minimax::get_board_end_state(&board) {
-            println!(
-                "TOTAL TIME: white={white_time:.2?}, black={black_time:.2?}, step={step_time:.2?}, num steps={current_step}"
-            );
-            return end_state;
+    let print_total_time = |record: &GameRecord, step_time: Duration| {
+        println!(
+            "TOTAL TIME: white={:.2?}, black={:.2?}, step={step_time:.2?}, num steps={}",
+            record.total_time(Side::White),
+            record.total_time(Side::Black),
+            record.ticks.len()
+        );
+    };
+    loop {
+        if let Some((end_state, reason)) = rules.end_state(&board, &progress) {
+            print_total_time(&record, step_time);
+            record.finish(end_state, reason);
+            return record;
         }
         let a = Instant::now();
-        let white_move = white_player(&board);
-        white_time += a.elapsed();
+        let white_move = white_player(&board, &progress, rng);
+        let white_time = a.elapsed();
         let b = Instant::now();
-        let black_move = black_player(&board);
-        black_time += b.elapsed();
+        let black_move = black_player(&board, &progress, rng);
+        let black_time = b.elapsed();
         debug_assert!(white_move.side() == Side::White);
         debug_assert!(black_move.side() == Side::Black);
+        let num_pieces = board.pieces().len();
         let c = Instant::now();
         board.step(&white_move, &black_move);
         step_time += c.elapsed();
-        current_step += 1;
-    }
-    println!(
-        "TOTAL TIME: white={white_time:.2?}, black={black_time:.2?}, step={step_time:.2?}, num steps={current_step}"
-    );
-    let material = minimax::evaluate_material_heuristic(&board);
-    match material.total_cmp(&0f32) {
-        Ordering::Less => EndState::Winner(Side::Black),
-        Ordering::Equal => EndState::Draw,
-        Ordering::Greater => EndState::Winner(Side::Black),
+        let captured = board.pieces().len() < num_pieces;
+        progress.push_step(&board, &white_move, &black_move, captured);
+        record.push_tick(RecordedTick {
+            white_move,
+            black_move,
+            white_time,
+            black_time,
+        });
     }
 }
 
@@ -270,9 +324,44 @@ fn main() -> OrError<()> {
     let run_all_epochs = args.iter().any(|arg| arg == "--all");
     let train = args.iter().any(|arg| arg == "--train");
     let no_versus = args.iter().any(|arg| arg == "--no-versus");
+    // versus games start from random Chess960 setups instead of positions of the training set
+    let chess960 = args.iter().any(|arg| arg == "--chess960");
+    // pass the printed seed back with --seed to replay the same random games
+    let seed = match args.iter().position(|arg| arg == "--seed") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|seed| seed.parse::<u64>().ok())
+            .ok_or_else(|| Error!("--seed expects an unsigned integer"))?,
+        None => rand::thread_rng().gen(),
+    };
     println!("Run all epochs? {run_all_epochs}");
     println!("train? {train}");
     println!("no versus? {no_versus}");
+    println!("chess960? {chess960}");
+    // directory to save every versus game to
+    let save_games_dir = match args.iter().position(|arg| arg == "--save-games") {
+        Some(i) => Some(
+            args.get(i + 1)
+                .ok_or_else(|| Error!("--save-games expects a directory"))?
+                .clone(),
+        ),
+        None => None,
+    };
+    if let Some(dir) = &save_games_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+    // versus games search for this long per move instead of to a fixed depth
+    let versus_budget = match args.iter().position(|arg| arg == "--search-millis") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|millis| millis.parse::<u64>().ok())
+            .map(|millis| SearchBudget::time(Duration::from_millis(millis)))
+            .ok_or_else(|| Error!("--search-millis expects an unsigned integer"))?,
+        None => SearchBudget::depth(SEARCH_DEPTH),
+    };
+    println!("seed: {seed}");
+    println!("save games to: {save_games_dir:?}");
+    println!("versus search budget: {versus_budget:?}");
     let code = include_str!("./model.py");
     let result: PyResult<_> = Python::with_gil(|py| {
         println!("Importing Python Code");
@@ -304,30 +393,65 @@ fn main() -> OrError<()> {
         let debug_stats = false;
         let num_versus_games = if run_all_epochs { 20 } else { 1 };
         let versus_stats = !no_versus;
-        let versus_stats_max_steps = if run_all_epochs { 1000 } else { 5 };
+        let versus_rules =
+            EndStateRules::standard().with_max_steps(Some(if run_all_epochs { 1000 } else { 5 }));
+        let mut setup_rng = StdRng::seed_from_u64(seed);
         for (i, lines) in reader.lines().chunks(chunk_size).into_iter().enumerate() {
             let before = Instant::now();
             let boards = lines
                 .map(|line| BoardState::parse_fen(line.unwrap().as_str()).unwrap())
                 .collect_vec();
             if versus_stats {
+                let versus_boards = if chess960 {
+                    (0..num_versus_games)
+                        .map(|_| Variant::random_chess960(&mut setup_rng).initial_state)
+                        .collect_vec()
+                } else {
+                    boards[..num_versus_games].to_vec()
+                };
                 println!("Computing versus stats versus heuristic");
                 let versus_stats_random = get_versus_stats(
-                    &boards[..num_versus_games],
-                    versus_stats_max_steps,
-                    |board, side| {
-                        move_from_minimax_with_sequential(board, side, &current_sequential)
+                    &versus_boards,
+                    &versus_rules,
+                    seed,
+                    |board, side, progress, _| {
+                        move_from_minimax_with_sequential(
+                            board,
+                            side,
+                            progress,
+                            &versus_rules,
+                            versus_budget,
+                            &current_sequential,
+                        )
                     },
-                    random_move,
+                    |board, side, _, rng| random_move(board, side, rng),
+                    save_games_dir.as_deref().map(|dir| (dir, "random", i)),
                 );
                 println!("{versus_stats_random}");
                 let versus_stats_heuristic = get_versus_stats(
-                    &boards[..num_versus_games],
-                    versus_stats_max_steps,
-                    |board, side| {
-                        move_from_minimax_with_sequential(board, side, &current_sequential)
+                    &versus_boards,
+                    &versus_rules,
+                    seed,
+                    |board, side, progress, _| {
+                        move_from_minimax_with_sequential(
+                            board,
+                            side,
+                            progress,
+                            &versus_rules,
+                            versus_budget,
+                            &current_sequential,
+                        )
+                    },
+                    |board, side, progress, _| {
+                        move_from_minimax_with_heuristic(
+                            board,
+                            side,
+                            progress,
+                            &versus_rules,
+                            versus_budget,
+                        )
                     },
-                    move_from_minimax_with_heuristic,
+                    save_games_dir.as_deref().map(|dir| (dir, "heuristic", i)),
                 );
                 println!("{versus_stats_heuristic}");
             }
@@ -344,16 +468,10 @@ fn main() -> OrError<()> {
                     let elapsed = before.elapsed();
                     // number of pieces and elapsed
                     if debug_stats {
-                        let best_piece = if let Some(best_move) = out.moves.first() {
-                            match best_move {
-                                BoardMove::None(_) => "None".to_owned(),
-                                BoardMove::LongCastle(_) => "LongCastle".to_owned(),
-                                BoardMove::ShortCastle(_) => "ShortCastle".to_owned(),
-                                BoardMove::Normal { piece, .. } => format!("{:?}", piece.kind),
-                            }
-                        } else {
-                            "N/A".to_owned()
-                        };
+                        let best_move = out
+                            .moves
+                            .first()
+                            .map_or("N/A".to_owned(), |best_move| best_move.to_string());
                         println!(
                             "{}, {}, {}, {}, {}, {}, {}",
                             board.pieces().len(),
@@ -361,8 +479,8 @@ fn main() -> OrError<()> {
                             out.num_regular_nodes,
                             out.num_quiescent_nodes,
                             elapsed.as_millis(),
-                            board.to_stationary_fen().unwrap(),
-                            best_piece,
+                            board.to_fen(),
+                            best_move,
                         );
                     }
                     score
@@ -0,0 +1,325 @@
+core!();
+
+use std::cmp::Ordering;
+
+use super::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EndState {
+    Winner(Side),
+    Draw,
+}
+
+// why a game ended with a given EndState
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EndReason {
+    KingCaptured,
+    OnlyKingsLeft,          // the kings either trade off or can never meet
+    InsufficientMaterial,   // neither side has more than a king and a minor piece
+    Repetition,             // the same position was reached too many times
+    NoProgressLimitReached, // too many steps since the last capture or pawn move
+    MaxStepsReached,        // adjudicated by material
+}
+
+impl EndState {
+    pub fn to_heuristic_score(self, win_score: HeuristicScore) -> HeuristicScore {
+        match self {
+            EndState::Winner(side) => match side {
+                Side::White => win_score,
+                Side::Black => -win_score,
+            },
+            EndState::Draw => 0f32,
+        }
+    }
+}
+
+// How far a game has gone, for the rules that depend on more than the current board.
+// Steps are pushed as they are played and popped when search takes them back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameProgress {
+    // one entry per position, starting with the initial one
+    history: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct HistoryEntry {
+    // the hash of the position if every piece is at rest (so the stationary layout and cooldowns)
+    position_key: Option<ZobristHash>,
+    steps_since_progress: u32, // since the last capture or pawn move
+    key_sum: ZobristHash,      // wrapping sum of the position keys up to and including this entry
+}
+
+impl HistoryEntry {
+    fn new(state: &BoardState, steps_since_progress: u32, previous_key_sum: ZobristHash) -> Self {
+        let position_key = position_key(state);
+        Self {
+            position_key,
+            steps_since_progress,
+            key_sum: previous_key_sum.wrapping_add(position_key.unwrap_or(0)),
+        }
+    }
+}
+
+impl GameProgress {
+    pub fn new(initial_state: &BoardState) -> Self {
+        Self {
+            history: vec![HistoryEntry::new(initial_state, 0, 0)],
+        }
+    }
+    pub fn steps(&self) -> u32 {
+        self.history.len() as u32 - 1
+    }
+    pub fn steps_since_progress(&self) -> u32 {
+        self.history.last().unwrap().steps_since_progress
+    }
+    // how many times the current position has been reached, or 0 while pieces are in flight
+    pub fn repetitions(&self) -> u32 {
+        let Some(key) = self.history.last().unwrap().position_key else {
+            return 0;
+        };
+        self.positions_since_progress()
+            .filter(|entry| entry.position_key == Some(key))
+            .count() as u32
+    }
+    // identifies everything the rules can tell apart about the history: the number of steps, the
+    // steps since progress and which positions were reached since then, in any order.
+    // two histories with the same key (and the same current board) end the same way
+    pub fn history_key(&self) -> ZobristHash {
+        let last = self.history.last().unwrap();
+        let num_positions = self.steps_since_progress() as usize + 1;
+        let key_sum_before = match self.history.len().checked_sub(num_positions + 1) {
+            Some(index) => self.history[index].key_sum,
+            None => 0,
+        };
+        combine_keys(&[
+            self.steps() as u64,
+            last.steps_since_progress as u64,
+            last.key_sum.wrapping_sub(key_sum_before),
+        ])
+    }
+    // positions from before a capture or a pawn move can not come back
+    fn positions_since_progress(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.history
+            .iter()
+            .rev()
+            .take(self.steps_since_progress() as usize + 1)
+    }
+    // state is the board after the step, and captured is whether the step took any piece
+    pub fn push_step(
+        &mut self,
+        state: &BoardState,
+        white_move: &BoardMove,
+        black_move: &BoardMove,
+        captured: bool,
+    ) {
+        let is_pawn_move = |board_move: &BoardMove| match board_move {
+            BoardMove::Normal { piece, .. } => piece.kind == PieceKind::Pawn,
+            _ => false,
+        };
+        let steps_since_progress =
+            if captured || is_pawn_move(white_move) || is_pawn_move(black_move) {
+                0
+            } else {
+                self.steps_since_progress() + 1
+            };
+        let key_sum = self.history.last().unwrap().key_sum;
+        self.history
+            .push(HistoryEntry::new(state, steps_since_progress, key_sum));
+    }
+    pub fn pop_step(&mut self) {
+        debug_assert!(self.history.len() > 1);
+        self.history.pop();
+    }
+}
+
+fn position_key(state: &BoardState) -> Option<ZobristHash> {
+    if state.is_all_pieces_stationary() {
+        Some(state.zobrist_hash())
+    } else {
+        None
+    }
+}
+
+// Which rules can end a game on top of king capture, which always does.
+// Search and versus play should be given the same rules so that search sees the games it plays
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EndStateRules {
+    pub insufficient_material: bool,
+    pub repetition_limit: Option<u32>, // drawn once a position is reached this many times
+    pub max_steps_without_progress: Option<u32>, // drawn once reached
+    pub max_steps: Option<u32>,        // adjudicated by material once reached
+    pub adjudication_margin: i32,      // a side needs more than this much material ahead to win
+}
+
+impl EndStateRules {
+    pub fn standard() -> Self {
+        Self {
+            insufficient_material: true,
+            repetition_limit: Some(3),
+            max_steps_without_progress: Some(200),
+            max_steps: None,
+            adjudication_margin: 0,
+        }
+    }
+    // only the rules that always apply, so games can go on forever
+    pub fn king_capture_only() -> Self {
+        Self {
+            insufficient_material: false,
+            repetition_limit: None,
+            max_steps_without_progress: None,
+            max_steps: None,
+            adjudication_margin: 0,
+        }
+    }
+    pub fn with_insufficient_material(self, insufficient_material: bool) -> Self {
+        Self {
+            insufficient_material,
+            ..self
+        }
+    }
+    pub fn with_repetition_limit(self, repetition_limit: Option<u32>) -> Self {
+        debug_assert!(repetition_limit.map_or(true, |limit| limit >= 2));
+        Self {
+            repetition_limit,
+            ..self
+        }
+    }
+    pub fn with_max_steps_without_progress(self, max_steps_without_progress: Option<u32>) -> Self {
+        Self {
+            max_steps_without_progress,
+            ..self
+        }
+    }
+    pub fn with_max_steps(self, max_steps: Option<u32>) -> Self {
+        Self { max_steps, ..self }
+    }
+    pub fn with_adjudication_margin(self, adjudication_margin: i32) -> Self {
+        debug_assert!(adjudication_margin >= 0);
+        Self {
+            adjudication_margin,
+            ..self
+        }
+    }
+    // games always end under these rules as long as there is a step limit,
+    // since there can only be so many captures and pawn moves
+    pub fn end_state(
+        &self,
+        state: &BoardState,
+        progress: &GameProgress,
+    ) -> Option<(EndState, EndReason)> {
+        if let Some(end) = get_board_end_state_with_reason(state) {
+            return Some(end);
+        }
+        if self.insufficient_material && has_insufficient_material(state) {
+            return Some((EndState::Draw, EndReason::InsufficientMaterial));
+        }
+        if self
+            .repetition_limit
+            .map_or(false, |limit| progress.repetitions() >= limit)
+        {
+            return Some((EndState::Draw, EndReason::Repetition));
+        }
+        if self
+            .max_steps_without_progress
+            .map_or(false, |limit| progress.steps_since_progress() >= limit)
+        {
+            return Some((EndState::Draw, EndReason::NoProgressLimitReached));
+        }
+        if self
+            .max_steps
+            .map_or(false, |limit| progress.steps() >= limit)
+        {
+            return Some((self.adjudicate(state), EndReason::MaxStepsReached));
+        }
+        None
+    }
+    pub fn adjudicate(&self, state: &BoardState) -> EndState {
+        let material = material_balance(state);
+        if material > self.adjudication_margin {
+            EndState::Winner(Side::White)
+        } else if material < -self.adjudication_margin {
+            EndState::Winner(Side::Black)
+        } else {
+            EndState::Draw
+        }
+    }
+}
+
+impl Default for EndStateRules {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+pub fn get_board_end_state(state: &BoardState) -> Option<EndState> {
+    get_board_end_state_with_reason(state).map(|(end_state, _)| end_state)
+}
+
+// only the rules that always apply, see EndStateRules for the rest
+pub fn get_board_end_state_with_reason(state: &BoardState) -> Option<(EndState, EndReason)> {
+    // if there are only 2 kings (and both with 0 cooldown), it's a draw
+    // if one side is missing a king, the other is the winner
+    // if both sides are missing a king, it's a draw (i.e. both somehow captured at the same time)
+
+    // we could also do other heuristics, but the model would not know how to play them
+    // if we put it as an end state (ex: K + R vs K is almost always a win)
+
+    let white_king = state
+        .pieces()
+        .iter()
+        .find(|p| p.kind == PieceKind::King && p.side == Side::White);
+    let black_king = state
+        .pieces()
+        .iter()
+        .find(|p| p.kind == PieceKind::King && p.side == Side::Black);
+    match (white_king, black_king) {
+        (None, None) => Some((EndState::Draw, EndReason::KingCaptured)),
+        (None, Some(_)) => Some((EndState::Winner(Side::Black), EndReason::KingCaptured)),
+        (Some(_), None) => Some((EndState::Winner(Side::White), EndReason::KingCaptured)),
+        (Some(white_king), Some(black_king)) => {
+            if state.pieces().len() == 2 {
+                if let PieceState::Stationary { position: white_position, cooldown: white_cooldown } = white_king.state &&
+                    let PieceState::Stationary { position: black_position, cooldown: black_cooldown } = black_king.state {
+                    let distance = (white_position - black_position).dist_linf();
+                    // adjacent kings: whoever can move first takes the other king
+                    let end_state = if distance <= 1 {
+                        match white_cooldown.cmp(&black_cooldown) {
+                            Ordering::Less => EndState::Winner(Side::White),
+                            Ordering::Equal => EndState::Draw,
+                            Ordering::Greater => EndState::Winner(Side::Black),
+                        }
+                    } else {
+                        EndState::Draw
+                    };
+                    Some((end_state, EndReason::OnlyKingsLeft))
+                } else {
+                    None
+                }
+            } else {
+                None
+            }
+        }
+    }
+}
+
+// K vs K, K+N vs K, K+B vs K and K+minor vs K+minor, once nothing is in flight and the kings are
+// apart (adjacent kings can still take each other, like when only the kings are left)
+fn has_insufficient_material(state: &BoardState) -> bool {
+    if !state.is_all_pieces_stationary() {
+        return false;
+    }
+    let mut kings = Vec::new();
+    let mut num_minor_pieces: EnumMap<Side, u32> = enum_map! { _ => 0 };
+    for piece in state.pieces() {
+        match (piece.kind, piece.state) {
+            (PieceKind::King, PieceState::Stationary { position, .. }) => kings.push(position),
+            (PieceKind::Knight | PieceKind::Bishop, _) => num_minor_pieces[piece.side] += 1,
+            _ => return false,
+        }
+    }
+    let kings_are_apart = match kings[..] {
+        [a, b] => (b - a).dist_linf() > 1,
+        _ => false,
+    };
+    kings_are_apart && num_minor_pieces.values().all(|&num| num <= 1)
+}
@@ -0,0 +1,265 @@
+core!();
+
+use super::*;
+
+// the end states of fresh games starting from each position
+fn end_states(fens: &[&str], rules: &EndStateRules) -> Vec<String> {
+    fens.iter()
+        .map(|fen| {
+            let board = BoardState::parse_fen(fen).unwrap();
+            let progress = GameProgress::new(&board);
+            format!("{fen}: {:?}", rules.end_state(&board, &progress))
+        })
+        .collect()
+}
+
+#[test]
+fn test_only_kings_left() {
+    let fens = [
+        "8/8/8/3kK3/8/8/8/8 - - -",
+        "8/8/8/3kK3/8/8/8/8 - d5:2 -",
+        "8/8/8/3kK3/8/8/8/8 - e5:2 -",
+        "8/8/8/3k1K2/8/8/8/8 - - -",
+    ];
+    expect!(
+        end_states(&fens, &EndStateRules::standard()),
+        r#"
+        [
+            "8/8/8/3kK3/8/8/8/8 - - -: Some((Draw, OnlyKingsLeft))",
+            "8/8/8/3kK3/8/8/8/8 - d5:2 -: Some((Winner(White), OnlyKingsLeft))",
+            "8/8/8/3kK3/8/8/8/8 - e5:2 -: Some((Winner(Black), OnlyKingsLeft))",
+            "8/8/8/3k1K2/8/8/8/8 - - -: Some((Draw, OnlyKingsLeft))",
+        ]"#
+    );
+}
+
+#[test]
+fn test_insufficient_material() {
+    let fens = [
+        "8/8/2k5/8/8/5K2/6B1/8 - - -",
+        "8/8/2k5/3n4/8/5K2/6B1/8 - - -",
+        "8/8/2k5/8/8/5K2/5NN1/8 - - -",
+        "8/8/2k5/8/8/5K2/5PB1/8 - - -",
+        "8/8/8/3kK3/8/8/6B1/8 - - -",
+    ];
+    expect!(
+        end_states(&fens, &EndStateRules::standard()),
+        r#"
+        [
+            "8/8/2k5/8/8/5K2/6B1/8 - - -: Some((Draw, InsufficientMaterial))",
+            "8/8/2k5/3n4/8/5K2/6B1/8 - - -: Some((Draw, InsufficientMaterial))",
+            "8/8/2k5/8/8/5K2/5NN1/8 - - -: None",
+            "8/8/2k5/8/8/5K2/5PB1/8 - - -: None",
+            "8/8/8/3kK3/8/8/6B1/8 - - -: None",
+        ]"#
+    );
+    let rules = EndStateRules::standard().with_insufficient_material(false);
+    assert_eq!(
+        end_states(&fens[..1], &rules),
+        [format!("{}: None", fens[0])]
+    );
+}
+
+#[test]
+fn test_repetition() {
+    // both knights go out and back as soon as they can, so the position keeps coming back
+    let mut board = BoardState::new_initial_state();
+    let rules = EndStateRules::standard();
+    let mut progress = GameProgress::new(&board);
+    let mut first_repetition = None;
+    while rules.end_state(&board, &progress).is_none() {
+        let shuffle = |side: Side, notations: [&str; 2]| {
+            notations
+                .into_iter()
+                .filter_map(|notation| board.parse_move(notation).ok())
+                .find(|board_move| board.can_move(board_move))
+                .unwrap_or(BoardMove::None(side))
+        };
+        let white_move = shuffle(Side::White, ["Wg1f3", "Wf3g1"]);
+        let black_move = shuffle(Side::Black, ["Bg8f6", "Bf6g8"]);
+        board.step(&white_move, &black_move);
+        progress.push_step(&board, &white_move, &black_move, false);
+        if first_repetition.is_none() && progress.repetitions() > 1 {
+            first_repetition = Some(progress.steps());
+        }
+    }
+    expect!(
+        (
+            first_repetition,
+            progress.steps(),
+            rules.end_state(&board, &progress)
+        ),
+        r#"
+        (
+            Some(
+                24,
+            ),
+            48,
+            Some(
+                (
+                    Draw,
+                    Repetition,
+                ),
+            ),
+        )"#
+    );
+}
+
+#[test]
+fn test_no_progress_limit() {
+    let board = BoardState::new_initial_state();
+    let rules = EndStateRules::standard().with_repetition_limit(None);
+    let mut progress = GameProgress::new(&board);
+    let passes = [BoardMove::None(Side::White), BoardMove::None(Side::Black)];
+    for _ in 0..199 {
+        progress.push_step(&board, &passes[0], &passes[1], false);
+    }
+    assert_eq!(rules.end_state(&board, &progress), None);
+    progress.push_step(&board, &passes[0], &passes[1], false);
+    assert_eq!(
+        rules.end_state(&board, &progress),
+        Some((EndState::Draw, EndReason::NoProgressLimitReached))
+    );
+    progress.pop_step();
+    let pawn_move = board.parse_move("Be7e5").unwrap();
+    progress.push_step(&board, &passes[0], &pawn_move, false);
+    progress.push_step(&board, &passes[0], &passes[1], true);
+    progress.push_step(&board, &passes[0], &passes[1], false);
+    assert_eq!(
+        (progress.steps(), progress.steps_since_progress()),
+        (202, 1)
+    );
+}
+
+#[test]
+fn test_adjudication_margin() {
+    // white is a rook for a pawn ahead
+    let board = BoardState::parse_fen("4k3/8/8/8/8/8/p7/R3K3").unwrap();
+    let mut progress = GameProgress::new(&board);
+    for _ in 0..10 {
+        progress.push_step(
+            &board,
+            &BoardMove::None(Side::White),
+            &BoardMove::None(Side::Black),
+            false,
+        );
+    }
+    let adjudicated = [None, Some(11), Some(10), Some(10)]
+        .into_iter()
+        .zip([0, 0, 0, 5])
+        .map(|(max_steps, margin)| {
+            let rules = EndStateRules::standard()
+                .with_repetition_limit(None)
+                .with_max_steps(max_steps)
+                .with_adjudication_margin(margin);
+            (max_steps, margin, rules.end_state(&board, &progress))
+        })
+        .collect::<Vec<_>>();
+    expect!(
+        adjudicated,
+        r#"
+        [
+            (
+                None,
+                0,
+                None,
+            ),
+            (
+                Some(
+                    11,
+                ),
+                0,
+                None,
+            ),
+            (
+                Some(
+                    10,
+                ),
+                0,
+                Some(
+                    (
+                        Winner(
+                            White,
+                        ),
+                        MaxStepsReached,
+                    ),
+                ),
+            ),
+            (
+                Some(
+                    10,
+                ),
+                5,
+                Some(
+                    (
+                        Draw,
+                        MaxStepsReached,
+                    ),
+                ),
+            ),
+        ]"#
+    );
+}
+
+#[test]
+fn test_search_uses_rules() {
+    let board = BoardState::parse_fen("4k3/8/8/8/8/8/8/R3K3").unwrap();
+    let rules = EndStateRules::standard().with_max_steps(Some(1));
+    let adjudicated = search_white_with_rules(
+        &board,
+        2,
+        &rules,
+        &GameProgress::new(&board),
+        evaluate_material_heuristic,
+    )
+    .unwrap();
+    assert_eq!(adjudicated.score, 100f32);
+    let searched = search_white_with_heuristic(&board, 2).unwrap();
+    assert_eq!(searched.score, 5f32);
+}
+
+#[test]
+fn test_search_white_only_ends_on_king_capture() {
+    // insufficient material, so only a draw under the standard rules
+    let board = BoardState::parse_fen("4k3/8/8/8/8/8/8/N3K3").unwrap();
+    let with_rules = search_white_with_rules(
+        &board,
+        2,
+        &EndStateRules::standard(),
+        &GameProgress::new(&board),
+        evaluate_material_heuristic,
+    )
+    .unwrap();
+    assert_eq!(with_rules.score, 0f32);
+    let searched = search_white_with_heuristic(&board, 2).unwrap();
+    assert_eq!(searched.score, 3f32);
+}
+
+#[test]
+fn test_search_sees_repetition() {
+    // white is a rook ahead, but both sides passed twice in a row
+    let board = BoardState::parse_fen("4k3/8/8/8/8/8/8/R3K3").unwrap();
+    let mut progress = GameProgress::new(&board);
+    let search = |progress: &GameProgress| {
+        search_white_with_rules(
+            &board,
+            2,
+            &EndStateRules::standard(),
+            progress,
+            evaluate_material_heuristic,
+        )
+        .unwrap()
+        .score
+    };
+    let mut scores = vec![search(&progress)];
+    for _ in 0..2 {
+        progress.push_step(
+            &board,
+            &BoardMove::None(Side::White),
+            &BoardMove::None(Side::Black),
+            false,
+        );
+        scores.push(search(&progress));
+    }
+    assert_eq!(scores, [5f32, 5f32, 0f32]);
+}
@@ -1,6 +1,7 @@
 core!();
 
 use anyhow::Ok;
+use rand::{rngs::StdRng, SeedableRng};
 
 use super::*;
 
@@ -35,11 +36,13 @@ fn to_compressed_debug(board_move: &BoardMove) -> String {
         BoardMove::Normal {
             piece: Piece { side, kind, state },
             target,
+            promotion,
         } => {
             if let PieceState::Stationary { position, .. } = state {
+                let promotion = promotion.map_or(String::new(), |kind| format!(" ={:?}", kind));
                 format!(
-                    "side={:?}, kind={:?}, move=[{}, {}] -> [{}, {}]",
-                    side, kind, position.x, position.y, target.x, target.y
+                    "side={:?}, kind={:?}, move=[{}, {}] -> [{}, {}]{}",
+                    side, kind, position.x, position.y, target.x, target.y, promotion
                 )
             } else {
                 String::from("Unknown: Moving?")
@@ -69,14 +72,16 @@ fn test_moves() {
         .map(|(_, num_leaves, _)| num_leaves)
         .sum::<u32>() as f32)
         / (BOARD_STATES.len() as f32);
-    expect!(average_leaves, "88668.164");
+    // these are the scores of stepping one tick at a time. skipping ahead used to remove every
+    // piece a lone moving knight passed close to, although a knight only captures on its target
+    expect!(average_leaves, "110972.586");
     expect!(
         board_moves,
         r#"
         [
             (
                 -13.0,
-                356860,
+                147002,
                 [
                     "side=White, kind=King, move=[4, 7] -> [4, 6]",
                     "side=Black, kind=Pawn, move=[7, 1] -> [7, 3]",
@@ -90,12 +95,12 @@ fn test_moves() {
             ),
             (
                 2.0,
-                24267,
+                87445,
                 [
-                    "side=White, kind=Queen, move=[1, 3] -> [1, 6]",
-                    "side=Black, kind=King, move=[3, 2] -> [4, 1]",
                     "side=White, kind=Rook, move=[4, 6] -> [4, 7]",
-                    "side=Black, kind=Pawn, move=[2, 2] -> [2, 3]",
+                    "side=Black, kind=Pawn, move=[2, 2] -> [1, 3]",
+                    "side=White, kind=Pawn, move=[4, 1] -> [4, 0] =Queen",
+                    "side=Black, kind=Knight, move=[6, 0] -> [4, 1]",
                     "None: White",
                     "None: Black",
                     "None: White",
@@ -104,27 +109,27 @@ fn test_moves() {
             ),
             (
                 -15.0,
-                64440,
+                23235,
                 [
                     "side=White, kind=Pawn, move=[2, 5] -> [1, 4]",
-                    "side=Black, kind=Rook, move=[0, 0] -> [1, 0]",
-                    "side=White, kind=Bishop, move=[4, 1] -> [3, 0]",
-                    "side=Black, kind=Queen, move=[4, 3] -> [4, 2]",
+                    "side=Black, kind=Pawn, move=[1, 4] -> [2, 5]",
+                    "side=White, kind=Bishop, move=[4, 1] -> [7, 4]",
+                    "side=Black, kind=Knight, move=[5, 4] -> [7, 5]",
                     "None: White",
-                    "side=Black, kind=Knight, move=[3, 5] -> [1, 4]",
+                    "None: Black",
                     "None: White",
                     "None: Black",
                 ],
             ),
             (
                 30.0,
-                35284,
+                55130,
                 [
-                    "side=White, kind=Queen, move=[6, 3] -> [5, 4]",
-                    "side=Black, kind=Bishop, move=[5, 2] -> [3, 4]",
-                    "side=White, kind=Knight, move=[4, 4] -> [6, 5]",
+                    "side=White, kind=Queen, move=[6, 3] -> [6, 5]",
                     "side=Black, kind=King, move=[1, 5] -> [0, 4]",
-                    "side=White, kind=Pawn, move=[1, 1] -> [1, 0]",
+                    "side=White, kind=Bishop, move=[7, 4] -> [5, 2]",
+                    "side=Black, kind=Bishop, move=[5, 2] -> [3, 4]",
+                    "side=White, kind=Pawn, move=[1, 1] -> [1, 0] =Queen",
                     "None: Black",
                     "None: White",
                     "None: Black",
@@ -132,47 +137,49 @@ fn test_moves() {
             ),
             (
                 -14.0,
-                277030,
+                604221,
                 [
                     "side=White, kind=King, move=[5, 2] -> [4, 1]",
                     "side=Black, kind=Knight, move=[4, 1] -> [2, 2]",
                     "side=White, kind=Rook, move=[7, 0] -> [0, 0]",
-                    "side=Black, kind=Queen, move=[0, 0] -> [1, 1]",
+                    "side=Black, kind=Pawn, move=[4, 6] -> [4, 7] =Queen",
                     "None: White",
-                    "side=Black, kind=Pawn, move=[4, 6] -> [4, 7]",
+                    "side=Black, kind=Queen, move=[0, 0] -> [1, 1]",
                     "None: White",
-                    "side=Black, kind=Pawn, move=[6, 6] -> [6, 7]",
+                    "side=Black, kind=Pawn, move=[6, 6] -> [6, 7] =Queen",
                 ],
             ),
             (
                 6.0,
-                39992,
+                237775,
                 [
                     "side=White, kind=Knight, move=[5, 0] -> [3, 1]",
-                    "side=Black, kind=Bishop, move=[6, 1] -> [5, 2]",
-                    "side=White, kind=Pawn, move=[1, 1] -> [1, 0]",
                     "side=Black, kind=Rook, move=[0, 7] -> [2, 7]",
-                    "None: White",
-                    "None: Black",
-                    "None: White",
-                    "side=Black, kind=Pawn, move=[4, 6] -> [4, 7]",
+                    "side=White, kind=Rook, move=[6, 3] -> [6, 1]",
+                    "side=Black, kind=Rook, move=[7, 7] -> [7, 4]",
+                    "side=White, kind=Pawn, move=[1, 1] -> [1, 0] =Queen",
+                    "side=Black, kind=Bishop, move=[6, 1] -> [5, 0]",
+                    "side=White, kind=Queen, move=[7, 4] -> [6, 3]",
+                    "side=Black, kind=Pawn, move=[4, 6] -> [4, 7] =Queen",
                 ],
             ),
             (
                 -8.0,
-                24237,
+                2587,
                 [
                     "side=White, kind=Pawn, move=[1, 6] -> [0, 5]",
-                    "side=Black, kind=Pawn, move=[0, 5] -> [0, 6]",
+                    "side=Black, kind=Pawn, move=[0, 5] -> [1, 6]",
                     "side=White, kind=Pawn, move=[5, 2] -> [5, 1]",
-                    "side=Black, kind=Rook, move=[1, 0] -> [0, 0]",
+                    "side=Black, kind=Knight, move=[4, 5] -> [5, 3]",
+                    "side=White, kind=Pawn, move=[5, 3] -> [5, 2]",
+                    "None: Black",
                     "None: White",
                     "None: Black",
                 ],
             ),
             (
-                3.0,
-                65362,
+                2.0,
+                8117,
                 [
                     "side=White, kind=Rook, move=[0, 5] -> [0, 6]",
                     "side=Black, kind=Pawn, move=[0, 2] -> [1, 3]",
@@ -186,27 +193,27 @@ fn test_moves() {
             ),
             (
                 -20.0,
-                14890,
+                101145,
                 [
-                    "side=White, kind=Bishop, move=[7, 0] -> [5, 2]",
+                    "side=White, kind=Bishop, move=[7, 0] -> [3, 4]",
                     "side=Black, kind=Rook, move=[3, 1] -> [2, 1]",
-                    "side=White, kind=Bishop, move=[6, 2] -> [5, 1]",
-                    "side=Black, kind=Bishop, move=[1, 3] -> [0, 2]",
+                    "side=White, kind=Bishop, move=[6, 2] -> [4, 4]",
+                    "side=Black, kind=Rook, move=[3, 4] -> [2, 4]",
                     "None: White",
-                    "side=Black, kind=Pawn, move=[2, 6] -> [2, 7]",
+                    "side=Black, kind=Pawn, move=[2, 6] -> [2, 7] =Queen",
                     "None: White",
                     "None: Black",
                 ],
             ),
             (
-                -7.0,
-                93397,
+                -5.0,
+                6672,
                 [
-                    "side=White, kind=Pawn, move=[3, 2] -> [4, 1]",
+                    "side=White, kind=Rook, move=[1, 2] -> [1, 1]",
                     "side=Black, kind=Bishop, move=[0, 3] -> [1, 2]",
-                    "side=White, kind=Rook, move=[6, 7] -> [6, 4]",
-                    "side=Black, kind=King, move=[7, 1] -> [6, 0]",
-                    "None: White",
+                    "side=White, kind=Pawn, move=[3, 2] -> [4, 1]",
+                    "side=Black, kind=Rook, move=[5, 6] -> [7, 6]",
+                    "side=White, kind=Bishop, move=[7, 6] -> [6, 5]",
                     "side=Black, kind=Queen, move=[0, 5] -> [4, 1]",
                     "None: White",
                     "None: Black",
@@ -214,26 +221,26 @@ fn test_moves() {
             ),
             (
                 3.0,
-                14068,
+                2833,
                 [
                     "side=White, kind=Queen, move=[0, 0] -> [1, 1]",
                     "side=Black, kind=Queen, move=[1, 1] -> [2, 2]",
                     "side=White, kind=Pawn, move=[4, 3] -> [4, 2]",
-                    "side=Black, kind=Bishop, move=[3, 0] -> [4, 1]",
-                    "side=White, kind=Knight, move=[6, 0] -> [4, 1]",
+                    "side=Black, kind=Knight, move=[6, 2] -> [4, 3]",
+                    "None: White",
                     "None: Black",
                     "None: White",
-                    "side=Black, kind=Knight, move=[6, 2] -> [4, 1]",
+                    "None: Black",
                 ],
             ),
             (
-                8.0,
-                54191,
+                7.0,
+                55509,
                 [
                     "side=White, kind=Rook, move=[1, 6] -> [3, 6]",
+                    "side=Black, kind=Pawn, move=[3, 6] -> [4, 7] =Queen",
+                    "side=White, kind=Rook, move=[4, 1] -> [2, 1]",
                     "side=Black, kind=Pawn, move=[6, 1] -> [5, 2]",
-                    "side=White, kind=Knight, move=[0, 3] -> [2, 2]",
-                    "side=Black, kind=Pawn, move=[3, 6] -> [4, 7]",
                     "None: White",
                     "None: Black",
                     "side=White, kind=King, move=[4, 6] -> [4, 7]",
@@ -243,3 +250,138 @@ fn test_moves() {
         ]"#
     );
 }
+
+fn symmetry_test_boards() -> Vec<BoardState> {
+    let constraints = PositionConstraints::midgame().with_moving_chance(0.3);
+    let mut rng = StdRng::seed_from_u64(7);
+    let generated = (0..20)
+        .map(|_| BoardState::generate_constrained_board_rng(&mut rng, &constraints).unwrap());
+    BOARD_STATES.iter().cloned().chain(generated).collect_vec()
+}
+
+#[test]
+fn test_heuristic_symmetry() {
+    for board in symmetry_test_boards() {
+        let score = evaluate_material_heuristic(&board);
+        let fen = board.to_fen();
+        assert_eq!(
+            evaluate_material_heuristic(&board.flip_colors()),
+            -score,
+            "{fen}"
+        );
+        assert_eq!(
+            evaluate_material_heuristic(&board.mirror_files()),
+            score,
+            "{fen}"
+        );
+    }
+}
+
+#[test]
+fn test_search_symmetry() {
+    let mut num_flipped_equal = 0;
+    for board in symmetry_test_boards() {
+        let fen = board.to_fen();
+        let score = search_white_with_heuristic(&board, 1).unwrap().score;
+        let mirrored = search_white_with_heuristic(&board.mirror_files(), 1)
+            .unwrap()
+            .score;
+        assert_eq!(mirrored, score, "{fen}");
+        // white commits to a move before black answers, so in the flipped board the original
+        // white gets to answer instead, which can only be better for it
+        let flipped = search_white_with_heuristic(&board.flip_colors(), 1)
+            .unwrap()
+            .score;
+        assert!(score <= -flipped, "{fen}");
+        if score == -flipped {
+            num_flipped_equal += 1;
+        }
+    }
+    // in the others, one side gains from seeing the other's move
+    expect!(num_flipped_equal, "22");
+}
+
+#[test]
+fn test_iterative_deepening() {
+    let reached_depths = BOARD_STATES
+        .iter()
+        .take(6)
+        .map(|board| {
+            let output = search_white(
+                board,
+                SearchBudget::nodes(100000),
+                evaluate_material_heuristic,
+            )
+            .unwrap();
+            // the result is the one of the deepest search that completed
+            let fixed = search_white_with_heuristic(board, output.search_depth).unwrap();
+            assert_eq!(output.score, fixed.score, "{}", board.to_fen());
+            output.search_depth
+        })
+        .collect_vec();
+    expect!(
+        reached_depths,
+        r#"
+        [
+            1,
+            2,
+            2,
+            1,
+            1,
+            1,
+        ]"#
+    );
+}
+
+#[test]
+fn test_search_budgets() {
+    let board = &BOARD_STATES[0];
+    let search =
+        |budget: SearchBudget| search_white(board, budget, evaluate_material_heuristic).unwrap();
+    // depth 1 is completed even when there is no time at all
+    let out_of_time = search(SearchBudget::time(Duration::ZERO));
+    assert_eq!(out_of_time.search_depth, 1);
+    assert_eq!(out_of_time.score, search(SearchBudget::depth(1)).score);
+    // a limit that is never reached deepens up to the maximum depth
+    let deepened = search(SearchBudget::depth(SEARCH_DEPTH).with_max_nodes(u64::MAX));
+    let fixed = search(SEARCH_DEPTH.into());
+    assert_eq!(deepened.search_depth, SEARCH_DEPTH);
+    assert_eq!(deepened.score, fixed.score);
+    assert_eq!(deepened.moves.len(), fixed.moves.len());
+}
+
+#[test]
+fn test_move_ordering_node_counts() {
+    let search = |move_ordering: bool| {
+        let config = SearchConfig::standard().with_move_ordering(move_ordering);
+        BOARD_STATES
+            .iter()
+            .map(|board| {
+                let progress = GameProgress::new(board);
+                let output = search_white_with_config(
+                    board,
+                    SEARCH_DEPTH,
+                    &config,
+                    &progress,
+                    evaluate_material_heuristic,
+                )
+                .unwrap();
+                (output.score, output.num_regular_nodes)
+            })
+            .collect_vec()
+    };
+    let (unordered, ordered) = (search(false), search(true));
+    // ordering only changes which of the equally good moves is found
+    let scores = |outputs: &[(HeuristicScore, u32)]| outputs.iter().map(|x| x.0).collect_vec();
+    assert_eq!(scores(&unordered), scores(&ordered));
+    let num_regular_nodes =
+        |outputs: &[(HeuristicScore, u32)]| outputs.iter().map(|x| x.1).sum::<u32>();
+    expect!(
+        (num_regular_nodes(&unordered), num_regular_nodes(&ordered)),
+        r#"
+        (
+            1709053,
+            616074,
+        )"#
+    );
+}
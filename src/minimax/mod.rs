@@ -1,25 +1,57 @@
 core!();
 
-use std::cmp::Ordering;
+use std::{
+    cell::{Cell, RefCell},
+    time::{Duration, Instant},
+};
 
 use crate::*;
 use enum_map::{enum_map, EnumMap};
 
+mod end_state;
+pub use end_state::*;
+
+mod transposition;
+pub use transposition::*;
+
+mod move_ordering;
+pub use move_ordering::*;
+
 #[cfg(test)]
 mod minimax_tests;
 
-lazy_static! {
-    pub static ref MATERIAL_VALUE: EnumMap<PieceKind, u32> = enum_map! {
-        PieceKind::Pawn => 1,
-        PieceKind::Knight => 3,
-        PieceKind::Bishop => 3,
-        PieceKind::Rook => 5,
-        PieceKind::Queen => 9,
-        PieceKind::King => 100,
-    };
-}
+#[cfg(test)]
+mod end_state_tests;
+
+#[cfg(test)]
+mod transposition_tests;
+
+#[cfg(test)]
+mod move_ordering_tests;
 
 const MAX_QUIESCENT_DEPTH: u32 = 2; // TODO (was 5)
+pub const DEFAULT_TRANSPOSITION_TABLE_SIZE: usize = 1 << 16;
+// how deep a search that is only limited by time or nodes is allowed to go
+pub const MAX_SEARCH_DEPTH: u32 = 32;
+
+thread_local! {
+    // searches are often shallow enough that allocating a table would take longer than the
+    // search itself, so every search on a thread reuses the same one
+    static TRANSPOSITION_TABLE: RefCell<TranspositionTable> =
+        RefCell::new(TranspositionTable::new(0));
+}
+
+// the thread's table, emptied and with the given size. it is taken out until the search gives
+// it back, so that a search started meanwhile (ex: by a leaf heuristic) gets one of its own
+fn take_transposition_table(size: usize) -> TranspositionTable {
+    let mut table = TRANSPOSITION_TABLE.with(|table| table.replace(TranspositionTable::new(0)));
+    if table.size() == size {
+        table.clear();
+        table
+    } else {
+        TranspositionTable::new(size)
+    }
+}
 
 type HeuristicScore = f32;
 
@@ -27,10 +59,14 @@ pub fn evaluate_material_heuristic(state: &BoardState) -> HeuristicScore {
     if let Some(end_state) = get_board_end_state(state) {
         return end_state.to_heuristic_score(100f32);
     }
+    material_balance(state) as f32
+}
+
+// white minus black material once every piece has come to rest
+pub fn material_balance(state: &BoardState) -> i32 {
     let mut state = state.clone();
     state.step_until_stationary_with_no_cooldown();
-    // count up material
-    let material_value: i32 = state
+    state
         .pieces()
         .iter()
         .map(|piece| {
@@ -41,69 +77,7 @@ pub fn evaluate_material_heuristic(state: &BoardState) -> HeuristicScore {
             let value = MATERIAL_VALUE[piece.kind] as i32;
             side * value
         })
-        .sum();
-    material_value as f32
-}
-
-pub fn get_board_end_state(state: &BoardState) -> Option<EndState> {
-    // if there are only 2 kings (and both with 0 cooldown), it's a draw
-    // if one side is missing a king, the other is the winner
-    // if both sides are missing a king, it's a draw (i.e. both somehow captured at the same time)
-
-    // we could also do other heuristics, but the model would not know how to play them
-    // if we put it as an end state (ex: K + R vs K is almost always a win)
-
-    let white_king = state
-        .pieces()
-        .iter()
-        .find(|p| p.kind == PieceKind::King && p.side == Side::White);
-    let black_king = state
-        .pieces()
-        .iter()
-        .find(|p| p.kind == PieceKind::King && p.side == Side::Black);
-    match (white_king, black_king) {
-        (None, None) => Some(EndState::Draw),
-        (None, Some(_)) => Some(EndState::Winner(Side::Black)),
-        (Some(_), None) => Some(EndState::Winner(Side::White)),
-        (Some(white_king), Some(black_king)) => {
-            if state.pieces().len() == 2 {
-                if let PieceState::Stationary { position: white_position, cooldown: white_cooldown } = white_king.state &&
-                    let PieceState::Stationary { position: black_position, cooldown: black_cooldown } = black_king.state {
-                    let distance = (white_position - black_position).dist_linf();
-                    if distance <= 1 {
-                        match white_cooldown.cmp(&black_cooldown) {
-                            Ordering::Less => Some(EndState::Winner(Side::White)),
-                            Ordering::Equal => Some(EndState::Draw),
-                            Ordering::Greater => Some(EndState::Winner(Side::White)),
-                        }
-                    } else {
-                        Some(EndState::Draw)
-                    }
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        }
-    }
-}
-
-pub enum EndState {
-    Winner(Side),
-    Draw,
-}
-
-impl EndState {
-    pub fn to_heuristic_score(self, win_score: HeuristicScore) -> HeuristicScore {
-        match self {
-            EndState::Winner(side) => match side {
-                Side::White => win_score,
-                Side::Black => -win_score,
-            },
-            EndState::Draw => 0f32,
-        }
-    }
+        .sum()
 }
 
 #[derive(Debug)]
@@ -114,6 +88,7 @@ pub struct MinimaxOutputInfo {
     pub num_leaves: u32,
     pub num_regular_nodes: u32,
     pub num_quiescent_nodes: u32,
+    pub num_transposition_hits: u32,
     pub moves: Vec<BoardMove>,
 }
 
@@ -191,6 +166,7 @@ impl MinimaxOutputInfo {
             search_depth,
             num_regular_nodes: output.num_regular_nodes(search_depth as i32),
             num_quiescent_nodes: output.num_quiescent_nodes(search_depth as i32),
+            num_transposition_hits: output.num_transposition_hits(),
         })
     }
 }
@@ -204,6 +180,8 @@ pub enum MinimaxOutput {
         next: Box<MinimaxOutput>,
         num_regular_nodes: u32,
         num_quiescent_nodes: u32,
+        // nodes whose result came from the transposition table instead of being searched
+        num_transposition_hits: u32,
     },
     Leaf {
         score: HeuristicScore,
@@ -243,79 +221,362 @@ impl MinimaxOutput {
             MinimaxOutput::Leaf { .. } => 0,
         }) + (if depth < 0 { 1u32 } else { 0u32 })
     }
+
+    pub fn num_transposition_hits(&self) -> u32 {
+        match self {
+            MinimaxOutput::Node {
+                num_transposition_hits,
+                ..
+            } => *num_transposition_hits,
+            MinimaxOutput::Leaf { .. } => 0,
+        }
+    }
 }
 
 pub fn search_white_with_heuristic(board: &BoardState, depth: u32) -> OrError<MinimaxOutputInfo> {
     search_white(board, depth, evaluate_material_heuristic)
 }
 
+// only king capture ends the game, see search_white_with_rules for the other rules
 pub fn search_white<F>(
     board: &BoardState,
-    depth: u32,
+    budget: impl Into<SearchBudget>,
     leaf_heuristic: F,
 ) -> OrError<MinimaxOutputInfo>
 where
     F: Fn(&BoardState) -> HeuristicScore,
 {
-    let output = white_move(
+    search_white_with_rules(
         board,
-        depth as i32,
-        f32::NEG_INFINITY,
-        f32::INFINITY,
-        &|_board, _board_move| 0f32, // TODO
-        &leaf_heuristic,
-    );
+        budget,
+        &EndStateRules::king_capture_only(),
+        &GameProgress::new(board),
+        leaf_heuristic,
+    )
+}
+
+// progress is how far the game has already gone, up to and including board
+pub fn search_white_with_rules<F>(
+    board: &BoardState,
+    budget: impl Into<SearchBudget>,
+    rules: &EndStateRules,
+    progress: &GameProgress,
+    leaf_heuristic: F,
+) -> OrError<MinimaxOutputInfo>
+where
+    F: Fn(&BoardState) -> HeuristicScore,
+{
+    let config = SearchConfig::standard().with_rules(*rules);
+    search_white_with_config(board, budget, &config, progress, leaf_heuristic)
+}
+
+// A budget with a time or node limit deepens one ply at a time and returns the deepest search
+// that completed, whose depth is the search_depth of the output. Depth 1 is always completed,
+// so that there is a move to play however small the budget is
+pub fn search_white_with_config<F>(
+    board: &BoardState,
+    budget: impl Into<SearchBudget>,
+    config: &SearchConfig,
+    progress: &GameProgress,
+    leaf_heuristic: F,
+) -> OrError<MinimaxOutputInfo>
+where
+    F: Fn(&BoardState) -> HeuristicScore,
+{
+    let budget = budget.into();
+    let params = SearchParams {
+        rules: &config.rules,
+        ordering: config
+            .move_ordering
+            .then(|| RefCell::new(MoveOrdering::new())),
+        leaf_heuristic: &leaf_heuristic,
+        table: RefCell::new(take_transposition_table(config.transposition_table_size)),
+        deadline: budget
+            .time_limit
+            .map(|time_limit| Instant::now() + time_limit),
+        max_nodes: budget.max_nodes,
+        num_nodes: Cell::new(0),
+        can_stop: Cell::new(false),
+        is_stopped: Cell::new(false),
+        root_depth: Cell::new(0),
+    };
+    // without a limit the shallower searches would only be thrown away
+    let first_depth = if budget.is_limited() {
+        1.min(budget.max_depth)
+    } else {
+        budget.max_depth
+    };
+    let mut completed = None;
+    for depth in first_depth..=budget.max_depth {
+        // moves are made and unmade on these copies instead of cloning the board for every child
+        let mut state = board.clone();
+        let mut search_progress = progress.clone();
+        params.root_depth.set(depth as i32);
+        let output = white_move(
+            &params,
+            &mut state,
+            &mut search_progress,
+            depth as i32,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+        );
+        if params.is_stopped.get() {
+            break;
+        }
+        completed = Some((output, depth));
+        params.can_stop.set(true);
+        if params.is_out_of_budget() {
+            break;
+        }
+    }
+    TRANSPOSITION_TABLE.with(|table| table.replace(params.table.into_inner()));
+    let (output, depth) = completed.unwrap();
     MinimaxOutputInfo::try_from(&output, board.clone(), depth)
 }
 
-fn white_move<F, G>(
+// How long a search may take: a maximum depth, and optionally a wall-clock time or a number of
+// visited nodes after which it stops deepening
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SearchBudget {
+    pub max_depth: u32,
+    pub time_limit: Option<Duration>,
+    pub max_nodes: Option<u64>,
+}
+
+impl SearchBudget {
+    pub fn depth(max_depth: u32) -> Self {
+        Self {
+            max_depth,
+            time_limit: None,
+            max_nodes: None,
+        }
+    }
+    pub fn time(time_limit: Duration) -> Self {
+        Self::depth(MAX_SEARCH_DEPTH).with_time_limit(time_limit)
+    }
+    pub fn nodes(max_nodes: u64) -> Self {
+        Self::depth(MAX_SEARCH_DEPTH).with_max_nodes(max_nodes)
+    }
+    pub fn with_max_depth(self, max_depth: u32) -> Self {
+        Self { max_depth, ..self }
+    }
+    pub fn with_time_limit(self, time_limit: Duration) -> Self {
+        Self {
+            time_limit: Some(time_limit),
+            ..self
+        }
+    }
+    pub fn with_max_nodes(self, max_nodes: u64) -> Self {
+        Self {
+            max_nodes: Some(max_nodes),
+            ..self
+        }
+    }
+    pub fn is_limited(&self) -> bool {
+        self.time_limit.is_some() || self.max_nodes.is_some()
+    }
+}
+
+impl From<u32> for SearchBudget {
+    fn from(max_depth: u32) -> Self {
+        Self::depth(max_depth)
+    }
+}
+
+// How a search is run, on top of the rules of the game
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SearchConfig {
+    pub rules: EndStateRules,
+    pub transposition_table_size: usize, // in entries, 0 to search without one
+    pub move_ordering: bool,             // false to search moves in the order they are generated
+}
+
+impl SearchConfig {
+    pub fn standard() -> Self {
+        Self {
+            rules: EndStateRules::standard(),
+            transposition_table_size: DEFAULT_TRANSPOSITION_TABLE_SIZE,
+            move_ordering: true,
+        }
+    }
+    pub fn with_rules(self, rules: EndStateRules) -> Self {
+        Self { rules, ..self }
+    }
+    pub fn with_move_ordering(self, move_ordering: bool) -> Self {
+        Self {
+            move_ordering,
+            ..self
+        }
+    }
+    pub fn with_transposition_table_size(self, transposition_table_size: usize) -> Self {
+        Self {
+            transposition_table_size,
+            ..self
+        }
+    }
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+// everything that stays the same for the whole search, and the table shared by all of it
+struct SearchParams<'a, G> {
+    rules: &'a EndStateRules,
+    ordering: Option<RefCell<MoveOrdering>>, // None to search moves in the order generated
+    leaf_heuristic: &'a G,
+    table: RefCell<TranspositionTable>,
+    deadline: Option<Instant>,
+    max_nodes: Option<u64>,
+    num_nodes: Cell<u64>,
+    can_stop: Cell<bool>, // false until a whole search has completed
+    // once set, every node returns at once and nothing more is stored in the table
+    is_stopped: Cell<bool>,
+    root_depth: Cell<i32>, // of the search in progress, to know the ply of a node
+}
+
+impl<G> SearchParams<'_, G> {
+    fn is_out_of_budget(&self) -> bool {
+        self.max_nodes
+            .map_or(false, |max_nodes| self.num_nodes.get() >= max_nodes)
+            || self
+                .deadline
+                .map_or(false, |deadline| Instant::now() >= deadline)
+    }
+    // how many moves of each side were made since the root, for a node at depth
+    fn ply(&self, depth: i32) -> usize {
+        (self.root_depth.get() - depth) as usize
+    }
+    fn sort_moves(&self, state: &BoardState, depth: i32, possible_moves: &mut [BoardMove]) {
+        if let Some(ordering) = &self.ordering {
+            ordering
+                .borrow()
+                .sort_moves(state, possible_moves, self.ply(depth));
+        }
+    }
+    fn record_cutoff(&self, state: &BoardState, depth: i32, board_move: &BoardMove) {
+        if let Some(ordering) = &self.ordering
+            && depth > 0
+        {
+            ordering
+                .borrow_mut()
+                .record_cutoff(state, board_move, self.ply(depth), depth);
+        }
+    }
+    // counts the node, and whether the search has to stop before searching it
+    fn should_stop(&self) -> bool {
+        self.num_nodes.set(self.num_nodes.get() + 1);
+        if self.can_stop.get() && !self.is_stopped.get() && self.is_out_of_budget() {
+            self.is_stopped.set(true);
+        }
+        self.is_stopped.get()
+    }
+    // the best move of this node in the previous, shallower search goes first
+    fn order_by_previous_search(
+        &self,
+        key: ZobristHash,
+        depth: i32,
+        possible_moves: &mut [BoardMove],
+    ) {
+        let table = self.table.borrow();
+        let Some(entry) = table.get(key).filter(|entry| entry.depth < depth) else {
+            return;
+        };
+        if let Some(i) = possible_moves
+            .iter()
+            .position(|board_move| is_same_move(board_move, &entry.best_move))
+        {
+            possible_moves[..=i].rotate_right(1);
+        }
+    }
+}
+
+// for moves of the same node, where a piece is identified by its state
+fn is_same_move(a: &BoardMove, b: &BoardMove) -> bool {
+    match (a, b) {
+        (
+            BoardMove::Normal {
+                piece,
+                target,
+                promotion,
+            },
+            BoardMove::Normal {
+                piece: other_piece,
+                target: other_target,
+                promotion: other_promotion,
+            },
+        ) => {
+            piece.state == other_piece.state
+                && target == other_target
+                && promotion == other_promotion
+        }
+        _ => std::mem::discriminant(a) == std::mem::discriminant(b) && a.side() == b.side(),
+    }
+}
+
+// the score of a node where the game is over, if it is
+fn get_end_score(
+    rules: &EndStateRules,
     state: &BoardState,
+    progress: &GameProgress,
+) -> Option<HeuristicScore> {
+    rules
+        .end_state(state, progress)
+        .map(|(end_state, _)| end_state.to_heuristic_score(100f32))
+}
+
+fn white_move<G>(
+    params: &SearchParams<G>,
+    state: &mut BoardState,
+    progress: &mut GameProgress,
     depth: i32,
     mut alpha: HeuristicScore,
     beta: HeuristicScore,
-    move_heuristic: &F,
-    leaf_heuristic: &G,
 ) -> MinimaxOutput
 where
-    F: Fn(&BoardState, &BoardMove) -> HeuristicScore,
     G: Fn(&BoardState) -> HeuristicScore,
 {
-    if get_board_end_state(state).is_some()
-        || (state.is_all_pieces_stationary_with_no_cooldown() && depth <= 0)
+    if params.should_stop() {
+        // the result is thrown away, so any score will do
+        return MinimaxOutput::Leaf { score: 0f32 };
+    }
+    if let Some(score) = get_end_score(params.rules, state, progress) {
+        return MinimaxOutput::Leaf { score };
+    }
+    if (state.is_all_pieces_stationary_with_no_cooldown() && depth <= 0)
         || depth <= -(MAX_QUIESCENT_DEPTH as i32)
     {
-        let score = leaf_heuristic(state);
+        let score = (params.leaf_heuristic)(state);
         return MinimaxOutput::Leaf { score };
     }
+    let key = white_node_key(state, progress);
+    if let Some(output) = params.table.borrow_mut().probe(key, depth, alpha, beta) {
+        return output;
+    }
+    let original_alpha = alpha;
     let mut best_move = BoardMove::None(Side::White);
     let mut best_opponent_move = None;
     let mut best_score = f32::MIN;
     let mut num_leaves = 0;
     let mut num_regular_nodes = 0;
     let mut num_quiescent_nodes = 0;
-    let possible_moves = if depth <= 0 {
+    let mut num_transposition_hits = 0;
+    let mut possible_moves = if depth <= 0 {
         state.get_sorted_quiescent_moves(Side::White, |kind| MATERIAL_VALUE[kind] as i32)
     } else {
         let mut possible_moves = state.get_all_possible_moves(Side::White);
-        // greater heuristic score = better for white, so we sort descending
-        util::sort_by_cached_f32_exn(&mut possible_moves, |possible_move| {
-            -move_heuristic(state, possible_move)
-        });
+        params.sort_moves(state, depth, &mut possible_moves);
         possible_moves
     };
+    params.order_by_previous_search(key, depth, &mut possible_moves);
     for board_move in possible_moves {
-        let opponent_move = black_move(
-            state,
-            depth,
-            alpha,
-            beta,
-            &board_move,
-            move_heuristic,
-            leaf_heuristic,
-        );
+        let opponent_move = black_move(params, state, progress, depth, alpha, beta, &board_move);
         num_leaves += opponent_move.num_leaves();
         num_regular_nodes += opponent_move.num_regular_nodes(depth);
         num_quiescent_nodes += opponent_move.num_quiescent_nodes(depth);
+        num_transposition_hits += opponent_move.num_transposition_hits();
         let score = opponent_move.score();
         if score > best_score {
             best_move = board_move;
@@ -324,78 +585,97 @@ where
         }
         alpha = alpha.max(best_score);
         if best_score >= beta {
+            params.record_cutoff(state, depth, &best_move);
             break;
         }
     }
-    MinimaxOutput::Node {
+    let output = MinimaxOutput::Node {
         best_move,
         best_score,
         num_leaves,
         next: Box::new(best_opponent_move.unwrap()),
         num_regular_nodes,
         num_quiescent_nodes,
+        num_transposition_hits,
+    };
+    if !params.is_stopped.get() {
+        params
+            .table
+            .borrow_mut()
+            .store(key, depth, original_alpha, beta, &output);
     }
+    output
 }
 
-fn black_move<F, G>(
-    state: &BoardState,
+fn black_move<G>(
+    params: &SearchParams<G>,
+    state: &mut BoardState,
+    progress: &mut GameProgress,
     depth: i32,
     alpha: HeuristicScore,
     mut beta: HeuristicScore,
     pending_white_move: &BoardMove,
-    move_heuristic: &F,
-    leaf_heuristic: &G,
 ) -> MinimaxOutput
 where
-    F: Fn(&BoardState, &BoardMove) -> HeuristicScore,
     G: Fn(&BoardState) -> HeuristicScore,
 {
-    if get_board_end_state(state).is_some()
-        || (state.is_all_pieces_stationary_with_no_cooldown() && depth <= 0)
+    if params.should_stop() {
+        // the result is thrown away, so any score will do
+        return MinimaxOutput::Leaf { score: 0f32 };
+    }
+    if let Some(score) = get_end_score(params.rules, state, progress) {
+        return MinimaxOutput::Leaf { score };
+    }
+    if (state.is_all_pieces_stationary_with_no_cooldown() && depth <= 0)
         || depth <= -(MAX_QUIESCENT_DEPTH as i32)
     {
         let score = evaluate_material_heuristic(state);
         return MinimaxOutput::Leaf { score };
     }
+    let key = black_node_key(state, progress, pending_white_move);
+    if let Some(output) = params.table.borrow_mut().probe(key, depth, alpha, beta) {
+        return output;
+    }
+    let original_beta = beta;
     let mut best_move = BoardMove::None(Side::Black);
     let mut best_opponent_move = None;
     let mut best_score = f32::MAX;
     let mut num_leaves = 0;
     let mut num_regular_nodes = 0;
     let mut num_quiescent_nodes = 0;
-    let possible_moves = if depth <= 0 {
+    let mut num_transposition_hits = 0;
+    let mut possible_moves = if depth <= 0 {
         state.get_sorted_quiescent_moves(Side::Black, |kind| MATERIAL_VALUE[kind] as i32)
     } else {
         let mut possible_moves = state.get_all_possible_moves(Side::Black);
-        // greater heuristic score = better for white, so we sort ascending
-        util::sort_by_cached_f32_exn(&mut possible_moves, |possible_move| {
-            move_heuristic(state, possible_move)
-        });
+        params.sort_moves(state, depth, &mut possible_moves);
         possible_moves
     };
+    params.order_by_previous_search(key, depth, &mut possible_moves);
+    let num_pieces = state.pieces().len();
     for board_move in possible_moves {
-        let mut new_state = state.clone();
-        if depth <= 0 {
-            new_state.apply_move(pending_white_move);
-            new_state.apply_move(&board_move);
-            // TODO-someday: may need to adjust
-            if !new_state.step_until_one_becomes_stationary() {
-                new_state.step_until_stationary_with_no_cooldown();
-            }
+        let undo = if depth <= 0 {
+            state.apply_reversibly(|state| {
+                state.apply_move(pending_white_move);
+                state.apply_move(&board_move);
+                // TODO-someday: may need to adjust
+                if !state.step_until_one_becomes_stationary() {
+                    state.step_until_stationary_with_no_cooldown();
+                }
+            })
         } else {
-            new_state.step(pending_white_move, &board_move);
-        }
-        let opponent_move = white_move(
-            &new_state,
-            depth - 1,
-            alpha,
-            beta,
-            move_heuristic,
-            leaf_heuristic,
-        );
+            state.step_reversibly(pending_white_move, &board_move)
+        };
+        // the quiescent steps above count as a single step
+        let captured = state.pieces().len() < num_pieces;
+        progress.push_step(state, pending_white_move, &board_move, captured);
+        let opponent_move = white_move(params, state, progress, depth - 1, alpha, beta);
+        progress.pop_step();
+        state.undo(undo);
         num_leaves += opponent_move.num_leaves();
         num_regular_nodes += opponent_move.num_regular_nodes(depth);
         num_quiescent_nodes += opponent_move.num_quiescent_nodes(depth);
+        num_transposition_hits += opponent_move.num_transposition_hits();
         let score = opponent_move.score();
         if score < best_score {
             best_score = score;
@@ -404,15 +684,24 @@ where
         }
         beta = beta.min(best_score);
         if best_score <= alpha {
+            params.record_cutoff(state, depth, &best_move);
             break;
         }
     }
-    MinimaxOutput::Node {
+    let output = MinimaxOutput::Node {
         best_move,
         best_score,
         num_leaves,
         next: Box::new(best_opponent_move.unwrap()),
         num_regular_nodes,
         num_quiescent_nodes,
+        num_transposition_hits,
+    };
+    if !params.is_stopped.get() {
+        params
+            .table
+            .borrow_mut()
+            .store(key, depth, alpha, original_beta, &output);
     }
+    output
 }
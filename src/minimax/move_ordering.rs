@@ -0,0 +1,140 @@
+core!();
+
+use std::cmp::Reverse;
+
+use super::*;
+
+const NUM_KILLERS: usize = 2; // per side and ply
+const NUM_SQUARES: usize = BOARD_SIZE * BOARD_SIZE;
+
+// the start and target squares of the most recent quiet moves that caused a cutoff
+type Killers = [Option<(Position, Position)>; NUM_KILLERS];
+
+// Groups of moves in the order they are searched, best first
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MoveClass {
+    Quiet,  // ordered by the history table
+    Killer, // caused a cutoff at the same ply of the search before
+    Dodge,  // moves a piece that is the target of an opponent's capture
+    Capture,
+}
+
+// What the search has learned about which quiet moves are good, kept for the whole search.
+// Captures and dodges are ordered from the board alone
+#[derive(Debug, Clone)]
+pub struct MoveOrdering {
+    killers: EnumMap<Side, Vec<Killers>>,
+    // indexed by start square, then target square
+    history: EnumMap<Side, Vec<u32>>,
+}
+
+impl MoveOrdering {
+    pub fn new() -> Self {
+        Self {
+            killers: enum_map! { _ => Vec::new() },
+            history: enum_map! { _ => vec![0; NUM_SQUARES * NUM_SQUARES] },
+        }
+    }
+
+    // a greater key is searched earlier: captures by most valuable victim then least valuable
+    // attacker, dodges by most valuable piece, killers by recency, and quiet moves by history
+    pub fn ordering_key(
+        &self,
+        state: &BoardState,
+        board_move: &BoardMove,
+        ply: usize,
+    ) -> (MoveClass, u32) {
+        let BoardMove::Normal { piece, target, .. } = board_move else {
+            return (MoveClass::Quiet, 0);
+        };
+        let PieceState::Stationary { position, .. } = piece.state else {
+            return (MoveClass::Quiet, 0);
+        };
+        let attacker_value = MATERIAL_VALUE[piece.kind];
+        if let Some(victim) = captured_kind(state, board_move) {
+            let most_valuable = MATERIAL_VALUE[PieceKind::King];
+            let value =
+                MATERIAL_VALUE[victim] * (most_valuable + 1) + most_valuable - attacker_value;
+            return (MoveClass::Capture, value);
+        }
+        if state.is_target_of_capture(&position) {
+            return (MoveClass::Dodge, attacker_value);
+        }
+        let squares = (position, *target);
+        let killers = self.killers[piece.side].get(ply);
+        if let Some(slot) =
+            killers.and_then(|killers| killers.iter().position(|k| *k == Some(squares)))
+        {
+            return (MoveClass::Killer, (NUM_KILLERS - slot) as u32);
+        }
+        (
+            MoveClass::Quiet,
+            self.history[piece.side][history_index(position, *target)],
+        )
+    }
+
+    pub fn sort_moves(&self, state: &BoardState, moves: &mut [BoardMove], ply: usize) {
+        // a stable sort, so that equal moves stay in the order they were generated in
+        moves.sort_by_cached_key(|board_move| Reverse(self.ordering_key(state, board_move, ply)));
+    }
+
+    // called when board_move made the opponent's previous move not worth searching further.
+    // captures are already searched first, so only quiet moves are remembered
+    pub fn record_cutoff(
+        &mut self,
+        state: &BoardState,
+        board_move: &BoardMove,
+        ply: usize,
+        depth: i32,
+    ) {
+        let BoardMove::Normal { piece, target, .. } = board_move else {
+            return;
+        };
+        let PieceState::Stationary { position, .. } = piece.state else {
+            return;
+        };
+        if captured_kind(state, board_move).is_some() {
+            return;
+        }
+        let squares = Some((position, *target));
+        let killers = &mut self.killers[piece.side];
+        if killers.len() <= ply {
+            killers.resize(ply + 1, [None; NUM_KILLERS]);
+        }
+        let killers = &mut killers[ply];
+        if killers[0] != squares {
+            killers.rotate_right(1);
+            killers[0] = squares;
+        }
+        // deeper cutoffs save more of the search
+        let bonus = (depth.max(1) * depth.max(1)) as u32;
+        let history = &mut self.history[piece.side][history_index(position, *target)];
+        *history = history.saturating_add(bonus);
+    }
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the kind of the opponent's piece standing on the target of the move, if any
+fn captured_kind(state: &BoardState, board_move: &BoardMove) -> Option<PieceKind> {
+    let BoardMove::Normal { piece, target, .. } = board_move else {
+        return None;
+    };
+    state.pieces().iter().find_map(|other| match other.state {
+        PieceState::Stationary { position, .. }
+            if other.side != piece.side && position == *target =>
+        {
+            Some(other.kind)
+        }
+        _ => None,
+    })
+}
+
+fn history_index(start: Position, target: Position) -> usize {
+    let square = |position: Position| position.y as usize * BOARD_SIZE + position.x as usize;
+    square(start) * NUM_SQUARES + square(target)
+}
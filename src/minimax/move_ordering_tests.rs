@@ -0,0 +1,118 @@
+core!();
+
+use super::*;
+
+// white can take the queen or a pawn, and the white queen is the target of a rook
+const FEN: &str = "4k3/8/8/3q1p2/4P3/8/8/3QK3 - - r:1:7:d1:2:0:1:0";
+
+fn ordered_moves(ordering: &MoveOrdering, board: &BoardState, ply: usize) -> Vec<String> {
+    let mut moves = board.get_all_possible_moves(Side::White);
+    ordering.sort_moves(board, &mut moves, ply);
+    moves
+        .iter()
+        .map(|board_move| {
+            let (class, value) = ordering.ordering_key(board, board_move, ply);
+            format!("{board_move} {class:?} {value}")
+        })
+        .collect()
+}
+
+#[test]
+fn test_captures_then_dodges() {
+    let board = BoardState::parse_fen(FEN).unwrap();
+    let ordering = MoveOrdering::new();
+    let moves = ordered_moves(&ordering, &board, 0);
+    let first_quiet = moves
+        .iter()
+        .position(|board_move| board_move.contains("Quiet"))
+        .unwrap();
+    expect!(
+        &moves[..=first_quiet],
+        r#"
+        [
+            "We4d5 Capture 1008",
+            "Wd1d5 Capture 1000",
+            "We4f5 Capture 200",
+            "Wd1c2 Dodge 9",
+            "Wd1b3 Dodge 9",
+            "Wd1a4 Dodge 9",
+            "Wd1e2 Dodge 9",
+            "Wd1f3 Dodge 9",
+            "Wd1g4 Dodge 9",
+            "Wd1h5 Dodge 9",
+            "Wd1c1 Dodge 9",
+            "Wd1b1 Dodge 9",
+            "Wd1a1 Dodge 9",
+            "Wd1d2 Dodge 9",
+            "Wd1d3 Dodge 9",
+            "Wd1d4 Dodge 9",
+            "We4e5 Quiet 0",
+        ]"#
+    );
+}
+
+#[test]
+fn test_killers_and_history() {
+    let board = BoardState::parse_fen(FEN).unwrap();
+    let mut ordering = MoveOrdering::new();
+    let king_moves =
+        ["We1f2", "We1e2", "We1f1"].map(|notation| board.parse_move(notation).unwrap());
+    // captures are searched first anyway, so they are not remembered
+    let capture = board.parse_move("We4d5").unwrap();
+    ordering.record_cutoff(&board, &capture, 0, 2);
+    for board_move in &king_moves {
+        ordering.record_cutoff(&board, board_move, 0, 2);
+    }
+    // the two most recent are killers at ply 0, and every one of them is in the history table
+    let keys_at_ply = |ply: usize| {
+        king_moves
+            .iter()
+            .chain([&capture])
+            .map(|board_move| ordering.ordering_key(&board, board_move, ply))
+            .collect_vec()
+    };
+    expect!(
+        keys_at_ply(0),
+        r#"
+        [
+            (
+                Quiet,
+                4,
+            ),
+            (
+                Killer,
+                1,
+            ),
+            (
+                Killer,
+                2,
+            ),
+            (
+                Capture,
+                1008,
+            ),
+        ]"#
+    );
+    expect!(
+        keys_at_ply(1),
+        r#"
+        [
+            (
+                Quiet,
+                4,
+            ),
+            (
+                Quiet,
+                4,
+            ),
+            (
+                Quiet,
+                4,
+            ),
+            (
+                Capture,
+                1008,
+            ),
+        ]"#
+    );
+}
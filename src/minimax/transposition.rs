@@ -0,0 +1,201 @@
+core!();
+
+use super::*;
+
+// What the score of a stored node says about its real value, since alpha-beta stops searching
+// a node as soon as it can no longer change the result
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower, // the node is worth at least the score (it failed high)
+    Upper, // the node is worth at most the score (it failed low)
+}
+
+#[derive(Debug, Clone)]
+pub struct TranspositionEntry {
+    pub key: ZobristHash,
+    pub depth: i32,
+    pub bound: Bound,
+    pub score: HeuristicScore,
+    pub best_move: BoardMove,
+    // the principal variation after best_move, so that an exact hit gives the same moves as
+    // searching again. only kept for exact entries
+    next_moves: Vec<BoardMove>,
+    generation: u32, // of the table when it was stored
+}
+
+// Fixed-size cache of searched nodes, indexed by key. a new entry always replaces the old one
+// in its slot, and a table of size 0 stores nothing
+#[derive(Debug, Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Option<TranspositionEntry>>,
+    // entries from before the last clear have an older generation and are ignored, so that
+    // clearing doesn't have to touch every slot
+    generation: u32,
+    num_hits: u32,
+}
+
+impl TranspositionTable {
+    pub fn new(size: usize) -> Self {
+        Self {
+            entries: vec![None; size],
+            generation: 0,
+            num_hits: 0,
+        }
+    }
+    pub fn clear(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        self.num_hits = 0;
+    }
+    pub fn size(&self) -> usize {
+        self.entries.len()
+    }
+    // how many probes ended a search early
+    pub fn num_hits(&self) -> u32 {
+        self.num_hits
+    }
+    pub fn get(&self, key: ZobristHash) -> Option<&TranspositionEntry> {
+        let entry = self.entries.get(self.index(key)?)?.as_ref()?;
+        if entry.key == key && entry.generation == self.generation {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+    // what searching the node again would return, if the stored entry is enough to know it.
+    // entries from other depths are never used, so that results do not depend on the table
+    pub(super) fn probe(
+        &mut self,
+        key: ZobristHash,
+        depth: i32,
+        alpha: HeuristicScore,
+        beta: HeuristicScore,
+    ) -> Option<MinimaxOutput> {
+        let entry = self.get(key).filter(|entry| entry.depth == depth)?;
+        let next_moves: &[BoardMove] = match entry.bound {
+            Bound::Exact => &entry.next_moves,
+            Bound::Lower if entry.score >= beta => &[],
+            Bound::Upper if entry.score <= alpha => &[],
+            _ => return None,
+        };
+        // the subtree was not searched this time, so it adds no leaves or nodes, only a hit
+        let next = next_moves.iter().rev().fold(
+            MinimaxOutput::Leaf { score: entry.score },
+            |next, board_move| MinimaxOutput::Node {
+                best_move: board_move.clone(),
+                best_score: entry.score,
+                num_leaves: 0,
+                next: Box::new(next),
+                num_regular_nodes: 0,
+                num_quiescent_nodes: 0,
+                num_transposition_hits: 0,
+            },
+        );
+        let output = MinimaxOutput::Node {
+            best_move: entry.best_move.clone(),
+            best_score: entry.score,
+            num_leaves: 0,
+            next: Box::new(next),
+            num_regular_nodes: 0,
+            num_quiescent_nodes: 0,
+            num_transposition_hits: 1,
+        };
+        self.num_hits += 1;
+        Some(output)
+    }
+    // alpha and beta are the window the node was searched with
+    pub(super) fn store(
+        &mut self,
+        key: ZobristHash,
+        depth: i32,
+        alpha: HeuristicScore,
+        beta: HeuristicScore,
+        output: &MinimaxOutput,
+    ) {
+        let MinimaxOutput::Node {
+            best_move,
+            best_score,
+            next,
+            ..
+        } = output
+        else {
+            return;
+        };
+        let Some(index) = self.index(key) else {
+            return;
+        };
+        let bound = if *best_score <= alpha {
+            Bound::Upper
+        } else if *best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        let mut next_moves = Vec::new();
+        if bound == Bound::Exact {
+            let mut current = next.as_ref();
+            while let MinimaxOutput::Node {
+                best_move, next, ..
+            } = current
+            {
+                next_moves.push(best_move.clone());
+                current = next;
+            }
+        }
+        self.entries[index] = Some(TranspositionEntry {
+            key,
+            depth,
+            bound,
+            score: *best_score,
+            best_move: best_move.clone(),
+            next_moves,
+            generation: self.generation,
+        });
+    }
+    fn index(&self, key: ZobristHash) -> Option<usize> {
+        if self.entries.is_empty() {
+            None
+        } else {
+            Some((key % self.entries.len() as u64) as usize)
+        }
+    }
+}
+
+// the key of a node where white is about to pick a move
+pub(super) fn white_node_key(state: &BoardState, progress: &GameProgress) -> ZobristHash {
+    combine_keys(&[
+        Side::White as u64,
+        state.zobrist_hash(),
+        progress.history_key(),
+    ])
+}
+
+// the key of a node where black answers pending_white_move
+pub(super) fn black_node_key(
+    state: &BoardState,
+    progress: &GameProgress,
+    pending_white_move: &BoardMove,
+) -> ZobristHash {
+    let move_values = match pending_white_move {
+        BoardMove::None(_) => [0, 0, 0],
+        BoardMove::LongCastle(_) => [1, 0, 0],
+        BoardMove::ShortCastle(_) => [2, 0, 0],
+        BoardMove::Normal {
+            piece,
+            target,
+            promotion,
+        } => [
+            piece_key(piece),
+            (target.y as u64) * (BOARD_SIZE as u64) + (target.x as u64),
+            promotion.map_or(0, |kind| 1 + kind as u64),
+        ],
+    };
+    combine_keys(&[
+        Side::Black as u64,
+        state.zobrist_hash(),
+        progress.history_key(),
+        move_values[0],
+        move_values[1],
+        move_values[2],
+    ])
+}
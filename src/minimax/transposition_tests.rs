@@ -0,0 +1,220 @@
+core!();
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::*;
+
+fn node(score: HeuristicScore) -> MinimaxOutput {
+    MinimaxOutput::Node {
+        best_move: BoardMove::None(Side::White),
+        best_score: score,
+        num_leaves: 10,
+        next: Box::new(MinimaxOutput::Leaf { score }),
+        num_regular_nodes: 5,
+        num_quiescent_nodes: 5,
+        num_transposition_hits: 0,
+    }
+}
+
+fn probe_summary(
+    table: &mut TranspositionTable,
+    key: ZobristHash,
+    depth: i32,
+    window: (HeuristicScore, HeuristicScore),
+) -> Option<(HeuristicScore, u32, u32)> {
+    table.probe(key, depth, window.0, window.1).map(|output| {
+        (
+            output.score(),
+            output.num_leaves(),
+            output.num_transposition_hits(),
+        )
+    })
+}
+
+#[test]
+fn test_bounds() {
+    let mut table = TranspositionTable::new(16);
+    // searched with the window (0, 10): exact inside it, and bounds outside of it
+    for (key, score) in [(1, 5f32), (2, 12f32), (3, -2f32)] {
+        table.store(key, 2, 0f32, 10f32, &node(score));
+    }
+    let bounds = [1, 2, 3].map(|key| table.get(key).map(|entry| entry.bound));
+    assert_eq!(
+        bounds,
+        [Some(Bound::Exact), Some(Bound::Lower), Some(Bound::Upper)]
+    );
+    let probes = [
+        (1, 2, (0f32, 10f32)),
+        (1, 1, (0f32, 10f32)),
+        (2, 2, (0f32, 10f32)),
+        (2, 2, (0f32, 20f32)),
+        (3, 2, (0f32, 10f32)),
+        (3, 2, (-5f32, 10f32)),
+        (4, 2, (0f32, 10f32)),
+    ]
+    .map(|(key, depth, window)| probe_summary(&mut table, key, depth, window));
+    expect!(
+        probes,
+        r#"
+        [
+            Some(
+                (
+                    5.0,
+                    0,
+                    1,
+                ),
+            ),
+            None,
+            Some(
+                (
+                    12.0,
+                    0,
+                    1,
+                ),
+            ),
+            None,
+            Some(
+                (
+                    -2.0,
+                    0,
+                    1,
+                ),
+            ),
+            None,
+            None,
+        ]"#
+    );
+    assert_eq!(table.num_hits(), 3);
+}
+
+#[test]
+fn test_empty_table() {
+    let mut table = TranspositionTable::new(0);
+    table.store(1, 2, 0f32, 10f32, &node(5f32));
+    assert!(table.get(1).is_none());
+    assert!(table.probe(1, 2, 0f32, 10f32).is_none());
+}
+
+#[test]
+fn test_clear() {
+    let mut table = TranspositionTable::new(16);
+    table.store(1, 2, 0f32, 10f32, &node(5f32));
+    assert!(table.probe(1, 2, 0f32, 10f32).is_some());
+    table.clear();
+    assert!(table.get(1).is_none());
+    assert_eq!(table.num_hits(), 0);
+    table.store(1, 2, 0f32, 10f32, &node(7f32));
+    assert_eq!(
+        probe_summary(&mut table, 1, 2, (0f32, 10f32)),
+        Some((7f32, 0, 1))
+    );
+}
+
+#[test]
+fn test_exact_hit_keeps_principal_variation() {
+    let board = BoardState::new_initial_state();
+    let moves = ["Wg1f3", "Bg8f6", "Wb1c3"].map(|notation| board.parse_move(notation).unwrap());
+    let mut output = MinimaxOutput::Leaf { score: 1f32 };
+    for board_move in moves.iter().rev() {
+        output = MinimaxOutput::Node {
+            best_move: board_move.clone(),
+            best_score: 1f32,
+            num_leaves: 10,
+            next: Box::new(output),
+            num_regular_nodes: 5,
+            num_quiescent_nodes: 5,
+            num_transposition_hits: 0,
+        };
+    }
+    let mut table = TranspositionTable::new(16);
+    table.store(1, 2, 0f32, 10f32, &output);
+    let hit = table.probe(1, 2, 0f32, 10f32).unwrap();
+    let info = MinimaxOutputInfo::try_from(&hit, board, 2).unwrap();
+    assert_eq!(
+        info.moves.iter().map(ToString::to_string).collect_vec(),
+        moves.iter().map(ToString::to_string).collect_vec()
+    );
+    // nothing below the hit was searched again
+    assert_eq!(
+        (
+            info.num_leaves,
+            info.num_regular_nodes,
+            info.num_transposition_hits
+        ),
+        (0, 1, 1)
+    );
+}
+
+#[test]
+fn test_history_key() {
+    // the same position after the knights went out and back, or after both sides passed twice
+    let board = BoardState::new_initial_state();
+    let passes = [BoardMove::None(Side::White), BoardMove::None(Side::Black)];
+    let mut passed = GameProgress::new(&board);
+    let mut shuffled = GameProgress::new(&board);
+    let mut shuffled_board = board.clone();
+    for notations in [("Wg1f3", "Bg8f6"), ("Wf3g1", "Bf6g8")] {
+        let white_move = shuffled_board.parse_move(notations.0).unwrap();
+        let black_move = shuffled_board.parse_move(notations.1).unwrap();
+        shuffled_board.step(&white_move, &black_move);
+        shuffled_board.step_until_stationary_with_no_cooldown();
+        shuffled.push_step(&shuffled_board, &white_move, &black_move, false);
+        passed.push_step(&board, &passes[0], &passes[1], false);
+    }
+    assert_eq!(shuffled_board, board);
+    // the initial position was seen 3 times when passing, but only twice when shuffling
+    assert_ne!(passed.history_key(), shuffled.history_key());
+    assert_eq!((passed.repetitions(), shuffled.repetitions()), (3, 2));
+    // the order in which positions were reached does not matter
+    let mut other_order = GameProgress::new(&board);
+    let mut in_order = GameProgress::new(&board);
+    let out = BoardState::parse_fen("rnbqkb1r/pppppppp/5n2/8/8/5N2/PPPPPPPP/RNBQKB1R").unwrap();
+    for (progress, states) in [
+        (&mut in_order, [&out, &board]),
+        (&mut other_order, [&board, &out]),
+    ] {
+        for state in states {
+            progress.push_step(state, &passes[0], &passes[1], false);
+        }
+        progress.push_step(&board, &passes[0], &passes[1], false);
+    }
+    assert_eq!(in_order.history_key(), other_order.history_key());
+}
+
+#[test]
+fn test_search_results_do_not_depend_on_table() {
+    let constraints = PositionConstraints::midgame().with_moving_chance(0.2);
+    let mut rng = StdRng::seed_from_u64(23);
+    for _ in 0..5 {
+        let board = BoardState::generate_constrained_board_rng(&mut rng, &constraints).unwrap();
+        let progress = GameProgress::new(&board);
+        let search = |transposition_table_size: usize| {
+            let config =
+                SearchConfig::standard().with_transposition_table_size(transposition_table_size);
+            search_white_with_config(&board, 2, &config, &progress, evaluate_material_heuristic)
+                .unwrap()
+        };
+        let without_table = search(0);
+        let with_table = search(DEFAULT_TRANSPOSITION_TABLE_SIZE);
+        let with_tiny_table = search(7);
+        let fen = board.to_fen();
+        for output in [&with_table, &with_tiny_table] {
+            assert_eq!(output.score, without_table.score, "{fen}");
+            assert_eq!(
+                output
+                    .moves
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+                without_table
+                    .moves
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+                "{fen}"
+            );
+        }
+        assert!(with_table.num_leaves <= without_table.num_leaves, "{fen}");
+        assert_eq!(without_table.num_transposition_hits, 0, "{fen}");
+    }
+}
@@ -0,0 +1,202 @@
+core!();
+
+use std::fmt::Write as _;
+
+use crate::*;
+
+#[cfg(test)]
+mod svg_tests;
+
+// Standalone SVG pictures of boards and games, for bug reports and design reviews.
+// Pieces are drawn with the Unicode chess glyphs, so any viewer with a font that has them works
+
+const SQUARE_SIZE: f32 = 60f32;
+const FRAME_SECONDS: f32 = 0.25; // how long each tick is shown in an animated game
+const LIGHT_SQUARE: &str = "#f0d9b5";
+const DARK_SQUARE: &str = "#b58863";
+const ARROW_COLOR: &str = "#3070c0";
+const COOLDOWN_COLOR: &str = "#30a030";
+const CAPTURE_COLOR: &str = "#d03030";
+
+// a board as it is after a tick, with the squares where pieces were captured during that tick
+#[derive(Debug, Clone)]
+pub struct SvgFrame {
+    pub board: BoardState,
+    pub captures: Vec<Position>,
+}
+
+impl SvgFrame {
+    pub fn new(board: BoardState) -> Self {
+        Self {
+            board,
+            captures: Vec::new(),
+        }
+    }
+}
+
+// the starting state, then one frame per tick of the moves
+pub fn game_to_svg_frames(
+    initial_state: &BoardState,
+    moves: &[(BoardMove, BoardMove)],
+) -> Vec<SvgFrame> {
+    let mut board = initial_state.clone();
+    let mut frames = vec![SvgFrame::new(board.clone())];
+    for (white_move, black_move) in moves {
+        let captures = board
+            .step_with_events(white_move, black_move)
+            .into_iter()
+            .filter_map(|event| match event {
+                StepEvent::Captured { victim, .. } => Some(match victim.state {
+                    PieceState::Stationary { position, .. } => position,
+                    PieceState::Moving { x, y, .. } => Position::round_and_from(x, y),
+                }),
+                _ => None,
+            })
+            .collect();
+        frames.push(SvgFrame {
+            board: board.clone(),
+            captures,
+        });
+    }
+    frames
+}
+
+pub fn board_to_svg(frame: &SvgFrame) -> String {
+    let mut svg = svg_header();
+    write_frame(&mut svg, frame);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// a single SVG that shows the frames one after the other, on a loop
+pub fn frames_to_animated_svg(frames: &[SvgFrame]) -> String {
+    let mut svg = svg_header();
+    let num_frames = frames.len();
+    for (i, frame) in frames.iter().enumerate() {
+        // discrete values are spread evenly over the duration, so each frame gets one slot
+        let visibility = (0..num_frames)
+            .map(|slot| if slot == i { "visible" } else { "hidden" })
+            .join(";");
+        writeln!(
+            svg,
+            r#"<g visibility="hidden"><animate attributeName="visibility" values="{}" dur="{:.2}s" calcMode="discrete" repeatCount="indefinite"/>"#,
+            visibility,
+            FRAME_SECONDS * num_frames as f32
+        )
+        .unwrap();
+        write_frame(&mut svg, frame);
+        svg.push_str("</g>\n");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+impl GameRecord {
+    pub fn to_svg_frames(&self) -> Vec<String> {
+        game_to_svg_frames(&self.initial_state, &self.moves())
+            .iter()
+            .map(board_to_svg)
+            .collect()
+    }
+    pub fn to_animated_svg(&self) -> String {
+        frames_to_animated_svg(&game_to_svg_frames(&self.initial_state, &self.moves()))
+    }
+    fn moves(&self) -> Vec<(BoardMove, BoardMove)> {
+        self.ticks
+            .iter()
+            .map(|tick| (tick.white_move.clone(), tick.black_move.clone()))
+            .collect()
+    }
+}
+
+fn svg_header() -> String {
+    let size = SQUARE_SIZE * BOARD_SIZE as f32;
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<defs><marker id="arrow" viewBox="0 0 10 10" refX="8" refY="5" markerWidth="4" markerHeight="4" orient="auto"><path d="M 0 0 L 10 5 L 0 10 z" fill="{ARROW_COLOR}"/></marker></defs>"#
+    )
+    .unwrap();
+    svg
+}
+
+// the center of a square, or of where a moving piece is between squares
+fn center(x: f32, y: f32) -> (f32, f32) {
+    ((x + 0.5) * SQUARE_SIZE, (y + 0.5) * SQUARE_SIZE)
+}
+
+fn write_frame(svg: &mut String, frame: &SvgFrame) {
+    let board = &frame.board;
+    for y in 0..BOARD_SIZE {
+        for x in 0..BOARD_SIZE {
+            let color = if (x + y) % 2 == 0 {
+                LIGHT_SQUARE
+            } else {
+                DARK_SQUARE
+            };
+            writeln!(
+                svg,
+                r#"<rect x="{:.1}" y="{:.1}" width="{SQUARE_SIZE:.1}" height="{SQUARE_SIZE:.1}" fill="{color}"/>"#,
+                x as f32 * SQUARE_SIZE,
+                y as f32 * SQUARE_SIZE
+            )
+            .unwrap();
+        }
+    }
+    let ring_radius = SQUARE_SIZE * 0.45;
+    for position in frame.captures.iter() {
+        let (cx, cy) = center(position.x as f32, position.y as f32);
+        writeln!(
+            svg,
+            r#"<circle cx="{cx:.1}" cy="{cy:.1}" r="{ring_radius:.1}" fill="{CAPTURE_COLOR}" fill-opacity="0.4"/>"#
+        )
+        .unwrap();
+    }
+    // arrows go under the pieces so that the glyphs stay readable
+    for piece in board.pieces() {
+        if let PieceState::Moving { x, y, target } = piece.state {
+            let (x1, y1) = center(x, y);
+            let (x2, y2) = center(target.target.x as f32, target.target.y as f32);
+            writeln!(
+                svg,
+                r#"<line x1="{x1:.1}" y1="{y1:.1}" x2="{x2:.1}" y2="{y2:.1}" stroke="{ARROW_COLOR}" stroke-width="4" marker-end="url(#arrow)"/>"#
+            )
+            .unwrap();
+        }
+    }
+    for piece in board.pieces() {
+        let glyph = RenderStyle::Unicode.piece_char(piece.side, piece.kind);
+        let (cx, cy, opacity) = match piece.state {
+            PieceState::Stationary { position, cooldown } => {
+                let (cx, cy) = center(position.x as f32, position.y as f32);
+                if cooldown > 0 {
+                    // the ring empties as the cooldown runs out, clockwise from the top
+                    let circumference = 2f32 * std::f32::consts::PI * ring_radius;
+                    let max_cooldown = board.config().piece_cooldown.max(cooldown);
+                    let filled = circumference * cooldown as f32 / max_cooldown as f32;
+                    writeln!(
+                        svg,
+                        r#"<circle cx="{cx:.1}" cy="{cy:.1}" r="{ring_radius:.1}" fill="none" stroke="{COOLDOWN_COLOR}" stroke-width="3" stroke-dasharray="{filled:.1} {circumference:.1}" transform="rotate(-90 {cx:.1} {cy:.1})"/>"#
+                    )
+                    .unwrap();
+                }
+                (cx, cy, 1f32)
+            }
+            PieceState::Moving { x, y, .. } => {
+                let (cx, cy) = center(x, y);
+                (cx, cy, 0.7f32)
+            }
+        };
+        writeln!(
+            svg,
+            r#"<text x="{cx:.1}" y="{cy:.1}" font-size="{:.1}" text-anchor="middle" dominant-baseline="central" opacity="{opacity:.1}">{glyph}</text>"#,
+            SQUARE_SIZE * 0.8
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+core!();
+
+use super::*;
+
+#[test]
+fn test_board_to_svg() {
+    // a king on cooldown, a rook on its way to h4 and a capture on a8
+    let board = BoardState::parse_fen("4k3/8/8/8/8/8/8/4K3 - e1:5 R:7:6:h4:2:0:0:-1").unwrap();
+    let frame = SvgFrame {
+        board,
+        captures: vec![Position::from_square_name("a8").unwrap()],
+    };
+    let svg = board_to_svg(&frame);
+    // everything but the squares, with single quotes so that the snapshot stays readable
+    expect!(
+        svg.lines()
+            .filter(|line| !line.starts_with("<rect"))
+            .map(|line| line.replace('"', "'"))
+            .collect::<Vec<_>>(),
+        r#"
+        [
+            "<svg xmlns='http://www.w3.org/2000/svg' width='480' height='480' viewBox='0 0 480 480'>",
+            "<defs><marker id='arrow' viewBox='0 0 10 10' refX='8' refY='5' markerWidth='4' markerHeight='4' orient='auto'><path d='M 0 0 L 10 5 L 0 10 z' fill='#3070c0'/></marker></defs>",
+            "<circle cx='30.0' cy='30.0' r='27.0' fill='#d03030' fill-opacity='0.4'/>",
+            "<line x1='450.0' y1='390.0' x2='450.0' y2='270.0' stroke='#3070c0' stroke-width='4' marker-end='url(#arrow)'/>",
+            "<text x='270.0' y='30.0' font-size='48.0' text-anchor='middle' dominant-baseline='central' opacity='1.0'>♚</text>",
+            "<circle cx='270.0' cy='450.0' r='27.0' fill='none' stroke='#30a030' stroke-width='3' stroke-dasharray='84.8 169.6' transform='rotate(-90 270.0 450.0)'/>",
+            "<text x='270.0' y='450.0' font-size='48.0' text-anchor='middle' dominant-baseline='central' opacity='1.0'>♔</text>",
+            "<text x='450.0' y='390.0' font-size='48.0' text-anchor='middle' dominant-baseline='central' opacity='0.7'>♖</text>",
+            "</svg>",
+        ]"#
+    );
+    assert_eq!(svg.matches("<rect").count(), BOARD_SIZE * BOARD_SIZE);
+}
+
+#[test]
+fn test_game_frames() {
+    // both rooks set off along the first rank at the same time and take each other halfway
+    let board = BoardState::parse_fen("k7/8/8/8/8/8/8/R6r").unwrap();
+    let mut record = GameRecord::new(board.clone());
+    for i in 0..5 {
+        let (white_move, black_move) = if i == 0 {
+            (
+                board.parse_move("Wa1h1").unwrap(),
+                board.parse_move("Bh1a1").unwrap(),
+            )
+        } else {
+            (BoardMove::None(Side::White), BoardMove::None(Side::Black))
+        };
+        record.push_tick(RecordedTick {
+            white_move,
+            black_move,
+            white_time: Default::default(),
+            black_time: Default::default(),
+        });
+    }
+    let moves = record
+        .ticks
+        .iter()
+        .map(|tick| (tick.white_move.clone(), tick.black_move.clone()))
+        .collect::<Vec<_>>();
+    let frames = game_to_svg_frames(&record.initial_state, &moves);
+    expect!(
+        frames
+            .iter()
+            .map(|frame| frame
+                .captures
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        r#"
+        [
+            [],
+            [],
+            [],
+            [],
+            [
+                "d1",
+                "e1",
+            ],
+            [],
+        ]"#
+    );
+    let svg_frames = record.to_svg_frames();
+    assert_eq!(svg_frames.len(), frames.len());
+    assert!(svg_frames
+        .iter()
+        .all(|svg| svg.matches("<svg").count() == 1));
+    let animated = record.to_animated_svg();
+    assert_eq!(animated.matches("<animate ").count(), frames.len());
+    assert_eq!(animated.matches("<svg").count(), 1);
+}